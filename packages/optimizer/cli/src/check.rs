@@ -0,0 +1,55 @@
+use crate::input::TransformArgs;
+use anyhow::{bail, Context, Error};
+use qwik_core::{apply_fixes, render_diagnostics, DiagnosticCategory, DiagnosticFormat};
+
+/// Fixing one problem can reveal or unblock another, but never loop for good.
+const MAX_FIX_PASSES: usize = 10;
+
+/// Runs the optimizer over the files, applying fixes to them when `fix` is set, and prints the
+/// diagnostics that are left. Fails when any of them is an error.
+pub fn run(
+	transform: &TransformArgs,
+	format: DiagnosticFormat,
+	fix: bool,
+	unsafe_fixes: bool,
+) -> Result<(), Error> {
+	let mut diagnostics = qwik_core::transform_modules(transform.to_options()?)?.diagnostics;
+	if fix {
+		for _ in 0..MAX_FIX_PASSES {
+			let options = transform.to_options()?;
+			let mut changed = false;
+			for (input, file) in options.input.iter().zip(&transform.files) {
+				let fixes = diagnostics
+					.iter()
+					.filter(|d| d.file == *input.path)
+					.flat_map(|d| &d.fixes)
+					.filter(|fix| fix.safe || unsafe_fixes);
+				let (code, applied) = apply_fixes(&input.code, fixes);
+				if applied > 0 {
+					std::fs::write(file, &code)
+						.with_context(|| format!("Writing {}", file.display()))?;
+					eprintln!("Applied {} fixes to {}", applied, file.display());
+					changed = true;
+				}
+			}
+			if !changed {
+				break;
+			}
+			diagnostics = qwik_core::transform_modules(transform.to_options()?)?.diagnostics;
+		}
+	}
+
+	let options = transform.to_options()?;
+	print!(
+		"{}",
+		render_diagnostics(&diagnostics, &options.input, &options.src_dir, format)
+	);
+	let errors = diagnostics
+		.iter()
+		.filter(|d| d.category != DiagnosticCategory::Warning)
+		.count();
+	if errors > 0 {
+		bail!("{} errors", errors);
+	}
+	Ok(())
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Error};
+use clap::Args;
+use qwik_core::{EmitMode, EntryStrategy, MinifyMode, TransformModulesOptions};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+/// Options shared by every command that runs the optimizer over a set of files.
+#[derive(Args, Debug)]
+pub struct TransformArgs {
+	/// Files to transform, relative to the current directory
+	#[arg(required = true)]
+	pub files: Vec<PathBuf>,
+
+	/// Source directory the input paths are made relative to
+	#[arg(long, default_value = ".")]
+	pub src_dir: PathBuf,
+
+	/// Root directory used for source map paths
+	#[arg(long)]
+	pub root_dir: Option<PathBuf>,
+
+	/// Entry strategy: inline, hoist, single, segment, component or smart
+	#[arg(long, default_value = "segment", value_parser = parse_serde_enum::<EntryStrategy>)]
+	pub entry_strategy: EntryStrategy,
+
+	/// Emit mode: prod, dev, lib, test or hmr
+	#[arg(long, default_value = "prod", value_parser = parse_serde_enum::<EmitMode>)]
+	pub mode: EmitMode,
+
+	/// Minify mode: simplify or none
+	#[arg(long, default_value = "simplify", value_parser = parse_serde_enum::<MinifyMode>)]
+	pub minify: MinifyMode,
+
+	/// Keep TypeScript syntax in the output
+	#[arg(long)]
+	pub no_transpile_ts: bool,
+
+	/// Keep JSX syntax in the output
+	#[arg(long)]
+	pub no_transpile_jsx: bool,
+
+	/// Transform for the client instead of the server
+	#[arg(long)]
+	pub client: bool,
+}
+
+impl TransformArgs {
+	pub fn to_options(&self) -> Result<TransformModulesOptions, Error> {
+		let src_dir = std::fs::canonicalize(&self.src_dir)
+			.with_context(|| format!("Resolving src dir {}", self.src_dir.display()))?;
+		let mut builder = TransformModulesOptions::builder(src_dir.to_string_lossy())
+			.minify(self.minify)
+			.transpile_ts(!self.no_transpile_ts)
+			.transpile_jsx(!self.no_transpile_jsx)
+			.entry_strategy(self.entry_strategy)
+			.mode(self.mode)
+			.is_server(!self.client);
+		if let Some(root_dir) = &self.root_dir {
+			builder = builder.root_dir(root_dir.to_string_lossy());
+		}
+		for file in &self.files {
+			let code = std::fs::read_to_string(file)
+				.with_context(|| format!("Reading {}", file.display()))?;
+			builder = builder.input(relative_to(&src_dir, file)?, code);
+		}
+		Ok(builder.build()?)
+	}
+}
+
+fn relative_to(src_dir: &Path, file: &Path) -> Result<String, Error> {
+	let file =
+		std::fs::canonicalize(file).with_context(|| format!("Resolving {}", file.display()))?;
+	let rel = file
+		.strip_prefix(src_dir)
+		.with_context(|| format!("{} is not inside {}", file.display(), src_dir.display()))?;
+	Ok(rel.to_string_lossy().replace('\\', "/"))
+}
+
+/// Parses a CLI value with the same names the JS options use, e.g. `--mode dev`.
+pub fn parse_serde_enum<T: DeserializeOwned>(value: &str) -> Result<T, String> {
+	serde_json::from_value(serde_json::Value::String(value.to_string()))
+		.map_err(|_| format!("unknown value `{}`", value))
+}
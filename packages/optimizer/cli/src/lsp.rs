@@ -0,0 +1,621 @@
+use anyhow::{Context, Error};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+	DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+	PublishDiagnostics,
+};
+use lsp_types::request::{CodeActionRequest, HoverRequest, Request as _};
+use lsp_types::{
+	CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+	CodeActionProviderCapability, DiagnosticSeverity, DidChangeTextDocumentParams,
+	DidCloseTextDocumentParams, DidOpenTextDocumentParams, Hover, HoverContents, HoverParams,
+	HoverProviderCapability, InitializeParams, MarkupContent, MarkupKind, NumberOrString, Position,
+	PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability,
+	TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+use qwik_core::{
+	AnalyzeOutput, DiagnosticCategory, EmitMode, EntryStrategy, Fix, SegmentAnalysis,
+	SourceLocation, TransformModulesOptions,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Serves the optimizer diagnostics of open documents over stdio. Documents are only analyzed,
+/// like `analyze_modules`, no code is generated.
+pub fn run(src_dir: Option<PathBuf>) -> Result<(), Error> {
+	let (connection, io_threads) = Connection::stdio();
+	let capabilities = serde_json::to_value(ServerCapabilities {
+		text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+		hover_provider: Some(HoverProviderCapability::Simple(true)),
+		code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+		..Default::default()
+	})?;
+	let params: InitializeParams = serde_json::from_value(connection.initialize(capabilities)?)?;
+	let src_dir = src_dir.or_else(|| workspace_root(&params));
+	Server {
+		connection: &connection,
+		src_dir,
+		documents: HashMap::new(),
+	}
+	.main_loop()?;
+	drop(connection);
+	io_threads.join()?;
+	Ok(())
+}
+
+#[allow(deprecated)]
+fn workspace_root(params: &InitializeParams) -> Option<PathBuf> {
+	params
+		.workspace_folders
+		.as_ref()
+		.and_then(|folders| folders.first())
+		.map(|folder| &folder.uri)
+		.or(params.root_uri.as_ref())
+		.and_then(uri_to_path)
+}
+
+struct Document {
+	text: String,
+	analysis: AnalyzeOutput,
+}
+
+struct Server<'a> {
+	connection: &'a Connection,
+	/// Documents are analyzed relative to this directory, or to their own directory when they
+	/// are outside of it
+	src_dir: Option<PathBuf>,
+	documents: HashMap<Uri, Document>,
+}
+
+impl Server<'_> {
+	fn main_loop(&mut self) -> Result<(), Error> {
+		for message in &self.connection.receiver {
+			match message {
+				Message::Request(request) => {
+					if self.connection.handle_shutdown(&request)? {
+						return Ok(());
+					}
+					self.handle_request(request)?;
+				}
+				Message::Notification(notification) => self.handle_notification(notification)?,
+				Message::Response(_) => {}
+			}
+		}
+		Ok(())
+	}
+
+	fn handle_request(&self, request: Request) -> Result<(), Error> {
+		match request.method.as_str() {
+			HoverRequest::METHOD => self.reply(request, |params: HoverParams| self.hover(&params)),
+			CodeActionRequest::METHOD => self.reply(request, |params: CodeActionParams| {
+				self.code_actions(&params)
+			}),
+			method => {
+				let message = format!("Unknown method {}", method);
+				self.send_response(Response::new_err(
+					request.id,
+					ErrorCode::MethodNotFound as i32,
+					message,
+				))
+			}
+		}
+	}
+
+	fn handle_notification(&mut self, notification: Notification) -> Result<(), Error> {
+		match notification.method.as_str() {
+			DidOpenTextDocument::METHOD => {
+				if let Some(params) = notification_params::<DidOpenTextDocumentParams>(notification)
+				{
+					self.update(params.text_document.uri, params.text_document.text)?;
+				}
+			}
+			DidChangeTextDocument::METHOD => {
+				if let Some(mut params) =
+					notification_params::<DidChangeTextDocumentParams>(notification)
+				{
+					// Full sync, the last change is the whole document
+					if let Some(change) = params.content_changes.pop() {
+						self.update(params.text_document.uri, change.text)?;
+					}
+				}
+			}
+			DidCloseTextDocument::METHOD => {
+				if let Some(params) =
+					notification_params::<DidCloseTextDocumentParams>(notification)
+				{
+					self.documents.remove(&params.text_document.uri);
+					self.publish(params.text_document.uri, vec![])?;
+				}
+			}
+			_ => {}
+		}
+		Ok(())
+	}
+
+	fn update(&mut self, uri: Uri, text: String) -> Result<(), Error> {
+		let analysis = match self.analyze(&uri, &text) {
+			Ok(analysis) => analysis,
+			Err(err) => {
+				eprintln!("Analyzing {}: {:#}", uri.as_str(), err);
+				return Ok(());
+			}
+		};
+		let diagnostics = analysis
+			.diagnostics
+			.iter()
+			.map(|diagnostic| to_lsp_diagnostic(diagnostic, &text))
+			.collect();
+		self.documents
+			.insert(uri.clone(), Document { text, analysis });
+		self.publish(uri, diagnostics)
+	}
+
+	fn analyze(&self, uri: &Uri, text: &str) -> Result<AnalyzeOutput, Error> {
+		let path = uri_to_path(uri).context("Only file URIs can be analyzed")?;
+		let (src_dir, rel_path) = match self
+			.src_dir
+			.as_deref()
+			.and_then(|dir| Some((dir, path.strip_prefix(dir).ok()?)))
+		{
+			Some((dir, rel_path)) => (dir, rel_path),
+			None => (
+				path.parent().unwrap_or_else(|| Path::new("/")),
+				Path::new(path.file_name().context("No file name")?),
+			),
+		};
+		let options = TransformModulesOptions::builder(src_dir.to_string_lossy())
+			.input(rel_path.to_string_lossy().replace('\\', "/"), text)
+			.mode(EmitMode::Dev)
+			.entry_strategy(EntryStrategy::Segment)
+			.transpile_ts(true)
+			.transpile_jsx(true)
+			.build()?;
+		Ok(qwik_core::analyze_modules(options)?)
+	}
+
+	/// The innermost segment at the cursor, with its symbol name and captures.
+	fn hover(&self, params: &HoverParams) -> Option<Hover> {
+		let position = &params.text_document_position_params;
+		let document = self.documents.get(&position.text_document.uri)?;
+		let (segment, range) = document
+			.analysis
+			.modules
+			.iter()
+			.flat_map(|module| &module.segments)
+			.filter_map(|segment| {
+				let range = to_range(segment.location.as_ref()?, &document.text);
+				contains(&range, &position.position).then_some((segment, range))
+			})
+			// Nested segments start after the ones around them
+			.max_by_key(|(_, range)| (range.start.line, range.start.character))?;
+		Some(Hover {
+			contents: HoverContents::Markup(MarkupContent {
+				kind: MarkupKind::Markdown,
+				value: describe_segment(segment),
+			}),
+			range: Some(range),
+		})
+	}
+
+	/// The fixes of the diagnostics in the request, and directives that suppress them.
+	fn code_actions(&self, params: &CodeActionParams) -> Vec<CodeActionOrCommand> {
+		let Some(document) = self.documents.get(&params.text_document.uri) else {
+			return vec![];
+		};
+		let uri = &params.text_document.uri;
+		let mut actions = vec![];
+		for diagnostic in &params.context.diagnostics {
+			let fixes: Vec<Fix> = diagnostic
+				.data
+				.clone()
+				.and_then(|data| serde_json::from_value(data).ok())
+				.unwrap_or_default();
+			for fix in fixes {
+				let edits = fix
+					.edits
+					.iter()
+					.map(|edit| TextEdit {
+						range: Range::new(
+							offset_position(&document.text, edit.range.start),
+							offset_position(&document.text, edit.range.end),
+						),
+						new_text: edit.replacement.clone(),
+					})
+					.collect();
+				actions.push(code_action(fix.message, diagnostic, uri, edits, fix.safe));
+			}
+
+			let Some(NumberOrString::String(code)) = &diagnostic.code else {
+				continue;
+			};
+			let line = diagnostic.range.start.line;
+			actions.push(code_action(
+				format!("Disable {} for this line", code),
+				diagnostic,
+				uri,
+				vec![TextEdit {
+					range: Range::new(Position::new(line, 0), Position::new(line, 0)),
+					new_text: disable_next_line(&document.text, line as usize, code),
+				}],
+				false,
+			));
+			actions.push(code_action(
+				format!("Disable {} for this file", code),
+				diagnostic,
+				uri,
+				vec![TextEdit {
+					range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+					new_text: format!("/* @qwik-disable-file {} */\n", code),
+				}],
+				false,
+			));
+		}
+		actions
+	}
+
+	fn publish(&self, uri: Uri, diagnostics: Vec<lsp_types::Diagnostic>) -> Result<(), Error> {
+		let params = PublishDiagnosticsParams {
+			uri,
+			diagnostics,
+			version: None,
+		};
+		self.connection
+			.sender
+			.send(Message::Notification(Notification::new(
+				PublishDiagnostics::METHOD.to_string(),
+				params,
+			)))?;
+		Ok(())
+	}
+
+	/// Replies with what `handler` returns for the params of `request`, or with `InvalidParams`
+	/// when they don't parse.
+	fn reply<P: DeserializeOwned, R: Serialize>(
+		&self,
+		request: Request,
+		handler: impl FnOnce(P) -> R,
+	) -> Result<(), Error> {
+		let response = match serde_json::from_value(request.params) {
+			Ok(params) => Response::new_ok(request.id, handler(params)),
+			Err(err) => Response::new_err(
+				request.id,
+				ErrorCode::InvalidParams as i32,
+				format!("Invalid params for {}: {}", request.method, err),
+			),
+		};
+		self.send_response(response)
+	}
+
+	fn send_response(&self, response: Response) -> Result<(), Error> {
+		self.connection.sender.send(Message::Response(response))?;
+		Ok(())
+	}
+}
+
+/// The params of `notification`, notifications have no response so bad ones are only logged.
+fn notification_params<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+	match serde_json::from_value(notification.params) {
+		Ok(params) => Some(params),
+		Err(err) => {
+			eprintln!(
+				"Ignoring {} with invalid params: {}",
+				notification.method, err
+			);
+			None
+		}
+	}
+}
+
+/// Diagnostics without a highlight are shown on the first line. The fixes travel in `data`
+/// to come back with the code action request.
+fn to_lsp_diagnostic(diagnostic: &qwik_core::Diagnostic, text: &str) -> lsp_types::Diagnostic {
+	let range = diagnostic
+		.highlights
+		.as_ref()
+		.and_then(|highlights| highlights.first())
+		.map_or_else(Range::default, |highlight| to_range(highlight, text));
+	lsp_types::Diagnostic {
+		range,
+		severity: Some(match diagnostic.category {
+			DiagnosticCategory::Warning => DiagnosticSeverity::WARNING,
+			DiagnosticCategory::Error | DiagnosticCategory::SourceError => {
+				DiagnosticSeverity::ERROR
+			}
+		}),
+		code: diagnostic.code.clone().map(NumberOrString::String),
+		source: Some("qwik".to_string()),
+		message: diagnostic.message.clone(),
+		data: (!diagnostic.fixes.is_empty())
+			.then(|| serde_json::to_value(&diagnostic.fixes).ok())
+			.flatten(),
+		..Default::default()
+	}
+}
+
+fn code_action(
+	title: String,
+	diagnostic: &lsp_types::Diagnostic,
+	uri: &Uri,
+	edits: Vec<TextEdit>,
+	preferred: bool,
+) -> CodeActionOrCommand {
+	CodeActionOrCommand::CodeAction(CodeAction {
+		title,
+		kind: Some(CodeActionKind::QUICKFIX),
+		diagnostics: Some(vec![diagnostic.clone()]),
+		edit: Some(WorkspaceEdit {
+			changes: Some(HashMap::from([(uri.clone(), edits)])),
+			..Default::default()
+		}),
+		is_preferred: Some(preferred),
+		..Default::default()
+	})
+}
+
+/// A `@qwik-disable-next-line` line for `line`, indented like it. Lines that start a JSX
+/// child get a JSX comment.
+fn disable_next_line(text: &str, line: usize, code: &str) -> String {
+	let lines: Vec<&str> = text.lines().collect();
+	let current = lines.get(line).copied().unwrap_or_default();
+	let indent = &current[..current.len() - current.trim_start().len()];
+	let in_jsx_children = current.trim_start().starts_with('<')
+		&& lines[..line.min(lines.len())]
+			.iter()
+			.rev()
+			.map(|line| line.trim_end())
+			.find(|line| !line.is_empty())
+			.is_some_and(|previous| previous.ends_with('>') || previous.ends_with('}'));
+	if in_jsx_children {
+		format!("{}{{/* @qwik-disable-next-line {} */}}\n", indent, code)
+	} else {
+		format!("{}// @qwik-disable-next-line {}\n", indent, code)
+	}
+}
+
+fn describe_segment(segment: &SegmentAnalysis) -> String {
+	let captures = if segment.captured.is_empty() {
+		"No captures".to_string()
+	} else {
+		let names: Vec<_> = segment
+			.captured
+			.iter()
+			.map(|capture| format!("`{}`", capture.name))
+			.collect();
+		format!("Captures {}", names.join(", "))
+	};
+	format!(
+		"**`{}`** ({}, `{}`)\n\n{}",
+		segment.name, segment.ctx_name, segment.display_name, captures
+	)
+}
+
+/// `location` as an LSP range. Its columns are display columns, with tabs counted as 4,
+/// LSP counts UTF-16 code units.
+fn to_range(location: &SourceLocation, text: &str) -> Range {
+	Range::new(
+		position(text, location.start_line, location.start_col - 1),
+		position(text, location.end_line, location.end_col),
+	)
+}
+
+/// The position of the 0-based display column `col` of the 1-based `line`.
+fn position(text: &str, line: usize, col: usize) -> Position {
+	let line = line.saturating_sub(1);
+	let mut width = 0;
+	let mut character = 0;
+	for c in text.lines().nth(line).unwrap_or_default().chars() {
+		if width >= col {
+			break;
+		}
+		width += if c == '\t' { 4 } else { 1 };
+		character += c.len_utf16();
+	}
+	Position::new(line as u32, character as u32)
+}
+
+/// The position of byte `offset` of `text`.
+fn offset_position(text: &str, offset: usize) -> Position {
+	let before = &text[..offset.min(text.len())];
+	let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+	Position::new(
+		before.matches('\n').count() as u32,
+		before[line_start..].encode_utf16().count() as u32,
+	)
+}
+
+fn contains(range: &Range, position: &Position) -> bool {
+	let key = |p: &Position| (p.line, p.character);
+	key(&range.start) <= key(position) && key(position) <= key(&range.end)
+}
+
+/// The path of a `file://` URI. Windows URIs like `file:///C:/app` or `file:///c%3A/app` lose
+/// the slash before the drive letter.
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+	let path = uri.as_str().strip_prefix("file://")?;
+	let mut bytes = vec![];
+	let mut rest = path.as_bytes();
+	while let Some((&b, tail)) = rest.split_first() {
+		match (b, tail) {
+			(b'%', [hi, lo, tail @ ..]) => {
+				let hex = std::str::from_utf8(&[*hi, *lo]).ok()?.to_string();
+				bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+				rest = tail;
+			}
+			_ => {
+				bytes.push(b);
+				rest = tail;
+			}
+		}
+	}
+	let path = String::from_utf8(bytes).ok()?;
+	let path = match path.as_bytes() {
+		[b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+		_ => &path,
+	};
+	Some(PathBuf::from(path))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use lsp_server::RequestId;
+	use lsp_types::{CodeActionContext, TextDocumentIdentifier};
+
+	fn uri(uri: &str) -> Uri {
+		uri.parse().unwrap()
+	}
+
+	#[test]
+	fn positions_count_utf16_and_tabs() {
+		let text = "a\n\tb😀c\n";
+		// Display column 5 is after the tab and `b`
+		assert_eq!(position(text, 2, 5), Position::new(1, 2));
+		assert_eq!(position(text, 2, 6), Position::new(1, 4));
+		assert_eq!(position(text, 9, 0), Position::new(8, 0));
+
+		let c = text.find('c').unwrap();
+		assert_eq!(offset_position(text, c), Position::new(1, 4));
+		assert_eq!(offset_position(text, 2), Position::new(1, 0));
+		assert_eq!(offset_position(text, 100), Position::new(2, 0));
+	}
+
+	#[test]
+	fn disable_next_line_matches_the_line() {
+		let text = "const a = 1;\n\treturn (\n\t\t<div>\n\t\t\t<button />\n\t\t</div>\n\t);\n";
+		assert_eq!(
+			disable_next_line(text, 1, "C02"),
+			"\t// @qwik-disable-next-line C02\n"
+		);
+		assert_eq!(
+			disable_next_line(text, 3, "jsx-key"),
+			"\t\t\t{/* @qwik-disable-next-line jsx-key */}\n"
+		);
+		assert_eq!(
+			disable_next_line(text, 2, "C02"),
+			"\t\t// @qwik-disable-next-line C02\n"
+		);
+		assert_eq!(
+			disable_next_line(text, 99, "C02"),
+			"// @qwik-disable-next-line C02\n"
+		);
+	}
+
+	#[test]
+	fn uri_to_path_decodes_unix_and_windows_uris() {
+		assert_eq!(
+			uri_to_path(&uri("file:///home/me/my%20app/src")),
+			Some(PathBuf::from("/home/me/my app/src"))
+		);
+		assert_eq!(
+			uri_to_path(&uri("file:///C:/Users/me/app")),
+			Some(PathBuf::from("C:/Users/me/app"))
+		);
+		assert_eq!(
+			uri_to_path(&uri("file:///c%3A/Users/me/app")),
+			Some(PathBuf::from("c:/Users/me/app"))
+		);
+		assert_eq!(uri_to_path(&uri("untitled:Untitled-1")), None);
+	}
+
+	#[test]
+	fn code_actions_apply_fixes_and_disable_rules() {
+		let (connection, _client) = Connection::memory();
+		let mut server = Server {
+			connection: &connection,
+			src_dir: Some(PathBuf::from("/app/src")),
+			documents: HashMap::new(),
+		};
+		let text = "import { component$ } from '@qwik.dev/core';\nexport const App = component$(() => {\n\treturn <button passive:click preventdefault:click onClick$={() => {}} />;\n});\n";
+		let document = uri("file:///app/src/app.tsx");
+		server.update(document.clone(), text.to_string()).unwrap();
+		let diagnostics: Vec<_> = server.documents[&document]
+			.analysis
+			.diagnostics
+			.iter()
+			.map(|diagnostic| to_lsp_diagnostic(diagnostic, text))
+			.collect();
+		assert_eq!(diagnostics.len(), 1);
+
+		let actions = server.code_actions(&CodeActionParams {
+			text_document: TextDocumentIdentifier {
+				uri: document.clone(),
+			},
+			range: diagnostics[0].range,
+			context: CodeActionContext {
+				diagnostics,
+				..Default::default()
+			},
+			work_done_progress_params: Default::default(),
+			partial_result_params: Default::default(),
+		});
+		let actions: Vec<_> = actions
+			.into_iter()
+			.map(|action| match action {
+				CodeActionOrCommand::CodeAction(action) => {
+					let edits = action.edit.unwrap().changes.unwrap().remove(&document);
+					(action.title, edits.unwrap())
+				}
+				CodeActionOrCommand::Command(_) => panic!("unexpected command"),
+			})
+			.collect();
+		let titles: Vec<_> = actions.iter().map(|(title, _)| title.as_str()).collect();
+		assert_eq!(
+			titles,
+			[
+				"Remove preventdefault:click",
+				"Disable preventdefault-passive-check for this line",
+				"Disable preventdefault-passive-check for this file",
+			]
+		);
+
+		let start = "\treturn <button passive:click".len() as u32;
+		let end = start + " preventdefault:click".len() as u32;
+		assert_eq!(
+			actions[0].1,
+			[TextEdit {
+				range: Range::new(Position::new(2, start), Position::new(2, end)),
+				new_text: String::new(),
+			}]
+		);
+		assert_eq!(
+			actions[1].1[0].new_text,
+			"\t// @qwik-disable-next-line preventdefault-passive-check\n"
+		);
+	}
+
+	#[test]
+	fn bad_requests_get_error_responses() {
+		let (connection, client) = Connection::memory();
+		let mut server = Server {
+			connection: &connection,
+			src_dir: None,
+			documents: HashMap::new(),
+		};
+		let error_code = |method: &str| {
+			let request = Request::new(
+				RequestId::from(1),
+				method.to_string(),
+				serde_json::json!(42),
+			);
+			server.handle_request(request).unwrap();
+			match client.receiver.try_recv().unwrap() {
+				Message::Response(response) => response.error.map(|error| error.code),
+				message => panic!("unexpected message {:?}", message),
+			}
+		};
+		assert_eq!(
+			error_code(HoverRequest::METHOD),
+			Some(ErrorCode::InvalidParams as i32)
+		);
+		assert_eq!(
+			error_code("qwik/unknown"),
+			Some(ErrorCode::MethodNotFound as i32)
+		);
+
+		let notification = Notification::new(DidOpenTextDocument::METHOD.to_string(), 42);
+		server.handle_notification(notification).unwrap();
+		assert!(client.receiver.try_recv().is_err());
+	}
+}
@@ -0,0 +1,121 @@
+#![deny(clippy::all)]
+#![deny(clippy::perf)]
+#![deny(clippy::nursery)]
+
+mod check;
+mod input;
+mod lsp;
+mod size;
+
+use anyhow::{Context, Error};
+use clap::{Parser, Subcommand};
+use input::{parse_serde_enum, TransformArgs};
+use qwik_core::{DiagnosticFormat, GraphFormat};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(
+	name = "qwik-optimizer",
+	version,
+	about = "Run the Qwik optimizer from the command line"
+)]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Print the segment graph of the transformed files
+	Graph {
+		#[command(flatten)]
+		transform: TransformArgs,
+
+		/// Output format: json, dot or mermaid
+		#[arg(long, default_value = "json", value_parser = parse_serde_enum::<GraphFormat>)]
+		format: GraphFormat,
+	},
+	/// Print the raw, gzip and brotli size of every emitted module
+	Size {
+		#[command(flatten)]
+		transform: TransformArgs,
+
+		/// Print the report as JSON instead of a table
+		#[arg(long)]
+		json: bool,
+
+		/// Also write q-manifest.json and q-sizes.json into this directory
+		#[arg(long)]
+		out_dir: Option<PathBuf>,
+	},
+	/// Print the diagnostics of the transformed files, failing on errors
+	Check {
+		#[command(flatten)]
+		transform: TransformArgs,
+
+		/// Output format: code-frame, sarif or json
+		#[arg(long, default_value = "code-frame", value_parser = parse_serde_enum::<DiagnosticFormat>)]
+		format: DiagnosticFormat,
+
+		/// Apply the safe fixes of the diagnostics to the files
+		#[arg(long)]
+		fix: bool,
+
+		/// With --fix, also apply fixes that may change behavior
+		#[arg(long, requires = "fix")]
+		unsafe_fixes: bool,
+	},
+	/// Serve the diagnostics of open documents over the Language Server Protocol on stdio
+	Lsp {
+		/// Source directory documents are analyzed relative to, the workspace root by default
+		#[arg(long)]
+		src_dir: Option<PathBuf>,
+	},
+	/// Print the JSON Schema of the optimizer options
+	Schema,
+}
+
+fn main() -> Result<(), Error> {
+	let cli = Cli::parse();
+	match cli.command {
+		Command::Graph { transform, format } => {
+			let output = qwik_core::transform_modules(transform.to_options()?)?;
+			print!("{}", output.segment_graph().render(format));
+		}
+		Command::Size {
+			transform,
+			json,
+			out_dir,
+		} => {
+			let mut options = transform.to_options()?;
+			options.size_report = true;
+			let output = qwik_core::transform_modules(options)?;
+			let report = output
+				.size_report
+				.as_ref()
+				.context("The optimizer returned no size report")?;
+			if let Some(out_dir) = out_dir {
+				size::write_report(&output, report, &out_dir)?;
+			}
+			if json {
+				println!("{}", serde_json::to_string_pretty(&report)?);
+			} else {
+				print!("{}", size::render_table(report));
+			}
+		}
+		Command::Check {
+			transform,
+			format,
+			fix,
+			unsafe_fixes,
+		} => check::run(&transform, format, fix, unsafe_fixes)?,
+		Command::Lsp { src_dir } => lsp::run(src_dir)?,
+		Command::Schema => {
+			println!(
+				"{}",
+				serde_json::to_string_pretty(&qwik_core::options_schema())?
+			);
+		}
+	}
+	Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Error};
+use qwik_core::{ModuleSizes, SizeReport, TransformOutput};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use swc_atoms::Atom;
+
+/// Writes `q-manifest.json` and `q-sizes.json` into `out_dir`.
+pub fn write_report(
+	output: &TransformOutput,
+	report: &SizeReport,
+	out_dir: &Path,
+) -> Result<(), Error> {
+	std::fs::create_dir_all(out_dir).with_context(|| format!("Creating {}", out_dir.display()))?;
+	let manifest = serde_json::to_string_pretty(&output.get_manifest())?;
+	std::fs::write(out_dir.join("q-manifest.json"), manifest)?;
+	let report = serde_json::to_string_pretty(report)?;
+	std::fs::write(out_dir.join("q-sizes.json"), report)?;
+	Ok(())
+}
+
+pub fn render_table(report: &SizeReport) -> String {
+	let mut out = String::new();
+	let modules: Vec<(String, ModuleSizes)> = report
+		.modules
+		.iter()
+		.map(|m| (m.path.clone(), m.sizes))
+		.collect();
+	write_section(&mut out, "Module", &modules);
+	write_section(&mut out, "Entry", &to_rows(&report.entries));
+	write_section(&mut out, "Origin", &to_rows(&report.origins));
+	write_section(&mut out, "Component", &to_rows(&report.components));
+	write_section(&mut out, "Total", &[("".to_string(), report.total)]);
+	out
+}
+
+fn to_rows(map: &BTreeMap<Atom, ModuleSizes>) -> Vec<(String, ModuleSizes)> {
+	map.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+}
+
+fn write_section(out: &mut String, title: &str, rows: &[(String, ModuleSizes)]) {
+	if rows.is_empty() {
+		return;
+	}
+	let width = rows
+		.iter()
+		.map(|(name, _)| name.len())
+		.chain(std::iter::once(title.len()))
+		.max()
+		.unwrap_or_default();
+	let _ = writeln!(
+		out,
+		"{:<width$}  {:>10}  {:>10}  {:>10}",
+		title,
+		"raw",
+		"gzip",
+		"brotli",
+		width = width
+	);
+	for (name, sizes) in rows {
+		let _ = writeln!(
+			out,
+			"{:<width$}  {:>10}  {:>10}  {:>10}",
+			name,
+			sizes.raw,
+			sizes.gzip,
+			sizes.brotli,
+			width = width
+		);
+	}
+	out.push('\n');
+}
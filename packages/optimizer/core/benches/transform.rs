@@ -206,6 +206,14 @@ fn transform_todo_app(b: &mut Bencher) {
           strip_ctx_name: None,
           strip_event_handlers: false,
           is_server: None,
+          stats: false,
+          explain: false,
+          manual_chunks: None,
+          size_report: false,
+          debug_dump: None,
+          verify: false,
+          rules: Default::default(),
+          custom_passes: Default::default(),
       })
   });
 }
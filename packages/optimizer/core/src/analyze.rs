@@ -0,0 +1,45 @@
+use crate::collector::GlobalCollect;
+use crate::parse::SegmentAnalysis;
+use crate::stats::TransformStats;
+use crate::utils::Diagnostic;
+use serde::{Deserialize, Serialize};
+use swc_atoms::Atom;
+
+/// What `analyze_modules` found in one input file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleAnalysis {
+	/// Path of the input file, relative to `src_dir`
+	pub path: String,
+	/// Same as the `segment` of the segment modules `transform_modules` would emit, except `size`,
+	/// which is 0 since no code is generated
+	pub segments: Vec<SegmentAnalysis>,
+	/// Sources of the static and dynamic imports of the root module
+	pub imports: Vec<Atom>,
+	/// Names exported by the root module
+	pub exports: Vec<Atom>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeOutput {
+	pub modules: Vec<ModuleAnalysis>,
+	pub diagnostics: Vec<Diagnostic>,
+	pub is_type_script: bool,
+	pub is_jsx: bool,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub stats: Vec<TransformStats>,
+}
+
+/// Exported names in declaration order, `default` included.
+pub fn export_names(collect: &GlobalCollect) -> Vec<Atom> {
+	collect
+		.exports
+		.iter()
+		.flat_map(|(local, info)| {
+			info.exported_names
+				.iter()
+				.map(move |name| name.clone().unwrap_or_else(|| local.clone()))
+		})
+		.collect()
+}
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 
 use swc_atoms::{atom, Atom};
 use swc_common::{Span, SyntaxContext, DUMMY_SP};
@@ -20,7 +21,8 @@ pub fn new_ident_from_id(id: &Id) -> ast::Ident {
 	ast::Ident::new(id.0.clone(), DUMMY_SP, id.1)
 }
 
-#[derive(Eq, PartialEq, Clone, Copy)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum ImportKind {
 	Named,
 	All,
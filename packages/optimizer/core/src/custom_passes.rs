@@ -0,0 +1,98 @@
+use std::fmt;
+use std::sync::Arc;
+use swc_atoms::Atom;
+use swc_common::util::take::Take;
+use swc_common::Mark;
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{Fold, FoldWith, VisitMut, VisitMutWith};
+
+/// Where in `transform_code` a custom pass runs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PassPoint {
+	/// Right after parsing (and `strip_exports`), before TypeScript and JSX are stripped
+	BeforeTypescript,
+	/// After the resolver, before imports are collected and `QwikTransform` extracts segments
+	BeforeQwikTransform,
+	/// On the root module once segments are extracted and root variables migrated, before hygiene
+	AfterSegmentation,
+	/// On each segment module right after `new_module` builds it
+	Segment,
+}
+
+/// What a custom pass knows about the module it runs on.
+pub struct PassContext<'a> {
+	/// Path of the input file, relative to `src_dir`
+	pub path: &'a str,
+	/// The segment symbol, only set for `PassPoint::Segment`
+	pub segment: Option<&'a Atom>,
+	pub unresolved_mark: Mark,
+	pub top_level_mark: Mark,
+}
+
+type PassFn = dyn Fn(&mut ast::Program, &PassContext) + Send + Sync;
+
+/// Extra passes that Rust embedders run at fixed points of `transform_code`.
+///
+/// Passes are registered as factories, so every input file gets a fresh visitor.
+#[derive(Clone, Default)]
+pub struct CustomPasses {
+	passes: Vec<(PassPoint, Arc<PassFn>)>,
+}
+
+impl CustomPasses {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn add(
+		&mut self,
+		point: PassPoint,
+		pass: impl Fn(&mut ast::Program, &PassContext) + Send + Sync + 'static,
+	) -> &mut Self {
+		self.passes.push((point, Arc::new(pass)));
+		self
+	}
+
+	pub fn add_visit_mut<V: VisitMut>(
+		&mut self,
+		point: PassPoint,
+		make: impl Fn(&PassContext) -> V + Send + Sync + 'static,
+	) -> &mut Self {
+		self.add(point, move |program, ctx| {
+			program.visit_mut_with(&mut make(ctx));
+		})
+	}
+
+	pub fn add_fold<F: Fold>(
+		&mut self,
+		point: PassPoint,
+		make: impl Fn(&PassContext) -> F + Send + Sync + 'static,
+	) -> &mut Self {
+		self.add(point, move |program, ctx| {
+			*program = program.take().fold_with(&mut make(ctx));
+		})
+	}
+
+	pub fn has(&self, point: PassPoint) -> bool {
+		self.passes.iter().any(|(p, _)| *p == point)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.passes.is_empty()
+	}
+
+	/// Runs the passes registered for `point`, in registration order.
+	pub fn run(&self, point: PassPoint, program: &mut ast::Program, ctx: &PassContext) {
+		for (_, pass) in self.passes.iter().filter(|(p, _)| *p == point) {
+			pass(program, ctx);
+		}
+	}
+}
+
+impl fmt::Debug for CustomPasses {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_list()
+			.entries(self.passes.iter().map(|(point, _)| point))
+			.finish()
+	}
+}
@@ -0,0 +1,173 @@
+use crate::parse::copy_comments;
+use anyhow::{bail, Context, Error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use swc_atoms::Atom;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{sync::Lrc, SourceMap};
+use swc_ecmascript::ast;
+use swc_ecmascript::codegen::text_writer::JsWriter;
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct DebugDumpOptions {
+	/// Also serialize the AST after every pass as JSON. Needs the `ast-dump` cargo feature.
+	pub ast: bool,
+	/// Write the dumps into this directory instead of returning them in `TransformOutput.debugDumps`.
+	/// Not available on wasm32-unknown-unknown, which has no file system.
+	pub out_dir: Option<String>,
+}
+
+/// The code of a root module or segment right after one pass of `transform_code`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PassDump {
+	pub path: String,
+	/// The segment symbol, `None` for the root module
+	pub segment: Option<Atom>,
+	/// Position of the dump among the dumps of the same module
+	pub index: usize,
+	pub pass: String,
+	pub code: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub ast: Option<String>,
+}
+
+/// Collects a `PassDump` after every pass when `debug_dump` is set. When disabled, nothing is emitted.
+pub struct PassDumper<'a> {
+	options: Option<&'a DebugDumpOptions>,
+	path: String,
+	source_map: Lrc<SourceMap>,
+	dumps: Vec<PassDump>,
+}
+
+impl<'a> PassDumper<'a> {
+	pub fn new(
+		options: Option<&'a DebugDumpOptions>,
+		path: &str,
+		source_map: Lrc<SourceMap>,
+	) -> Self {
+		Self {
+			options,
+			path: path.to_string(),
+			source_map,
+			dumps: vec![],
+		}
+	}
+
+	pub fn dump(&mut self, pass: &str, program: &ast::Program, comments: &SingleThreadedComments) {
+		self.push(None, pass, program, comments);
+	}
+
+	pub fn dump_segment(
+		&mut self,
+		segment: &Atom,
+		pass: &str,
+		module: &ast::Module,
+		comments: &SingleThreadedComments,
+	) {
+		if self.options.is_some() {
+			let program = ast::Program::Module(module.clone());
+			self.push(Some(segment), pass, &program, comments);
+		}
+	}
+
+	fn push(
+		&mut self,
+		segment: Option<&Atom>,
+		pass: &str,
+		program: &ast::Program,
+		comments: &SingleThreadedComments,
+	) {
+		let Some(options) = self.options else {
+			return;
+		};
+		let index = self
+			.dumps
+			.iter()
+			.filter(|d| d.segment.as_ref() == segment)
+			.count();
+		let code = emit_program(&self.source_map, program, comments)
+			.unwrap_or_else(|err| format!("/* codegen failed: {} */", err));
+		let ast = options.ast.then(|| ast_json(program));
+		self.dumps.push(PassDump {
+			path: self.path.clone(),
+			segment: segment.cloned(),
+			index,
+			pass: pass.to_string(),
+			code,
+			ast,
+		});
+	}
+
+	/// Returns the dumps, or writes them to `out_dir` as `<path>/[<segment>/]<index>_<pass>.js`.
+	pub fn finish(self) -> Result<Vec<PassDump>, Error> {
+		let Some(out_dir) = self.options.and_then(|o| o.out_dir.as_ref()) else {
+			return Ok(self.dumps);
+		};
+		for dump in &self.dumps {
+			let mut relative = PathBuf::from(&dump.path);
+			if let Some(segment) = &dump.segment {
+				relative.push(segment.as_str());
+			}
+			let stem = format!("{:02}_{}", dump.index, dump.pass);
+			relative.push(format!("{}.js", stem));
+			// `..`, roots and prefixes would write outside of `out_dir`
+			if !relative
+				.components()
+				.all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+			{
+				bail!(
+					"Debug dump {} would be written outside of outDir",
+					relative.display()
+				);
+			}
+			let file = Path::new(out_dir).join(&relative);
+			let dir = file.parent().unwrap_or_else(|| Path::new(out_dir));
+			std::fs::create_dir_all(dir)
+				.with_context(|| format!("Creating debug dump dir {}", dir.display()))?;
+			std::fs::write(&file, &dump.code)
+				.with_context(|| format!("Writing debug dump {}", file.display()))?;
+			if let Some(ast) = &dump.ast {
+				let file = dir.join(format!("{}.json", stem));
+				std::fs::write(&file, ast)
+					.with_context(|| format!("Writing debug dump {}", file.display()))?;
+			}
+		}
+		Ok(vec![])
+	}
+}
+
+#[cfg(feature = "ast-dump")]
+fn ast_json(program: &ast::Program) -> String {
+	serde_json::to_string_pretty(program).unwrap_or_else(|err| err.to_string())
+}
+
+/// `TransformModulesOptions::validate` rejects `ast` without the feature
+#[cfg(not(feature = "ast-dump"))]
+const fn ast_json(_: &ast::Program) -> String {
+	String::new()
+}
+
+/// Emits a program without source maps, on a copy of the comments the real codegen still needs.
+fn emit_program(
+	source_map: &Lrc<SourceMap>,
+	program: &ast::Program,
+	comments: &SingleThreadedComments,
+) -> Result<String, Error> {
+	let comments = copy_comments(comments);
+
+	let mut buf = Vec::new();
+	{
+		let writer = Box::new(JsWriter::new(Lrc::clone(source_map), "\n", &mut buf, None));
+		let mut emitter = swc_ecmascript::codegen::Emitter {
+			cfg: swc_ecmascript::codegen::Config::default(),
+			comments: Some(&comments),
+			cm: Lrc::clone(source_map),
+			wr: writer,
+		};
+		emitter.emit_program(program)?;
+	}
+	Ok(String::from_utf8(buf)?)
+}
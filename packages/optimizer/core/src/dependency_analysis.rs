@@ -4,6 +4,7 @@ use swc_ecmascript::ast;
 use swc_ecmascript::visit::VisitWith;
 
 use crate::collector::{GlobalCollect, Id, IdentCollector};
+use crate::explain::{Decision, MigrationReason};
 
 /// Root-level declaration associated with an identifier.
 #[derive(Clone, Debug)]
@@ -504,6 +505,68 @@ pub fn find_migratable_vars(
 	migratable
 }
 
+/// Explains the result of `find_migratable_vars`, once for every segment that uses a root variable.
+/// Returns `(segment index, decision)` pairs.
+pub fn explain_migration(
+	root_dependencies: &HashMap<Id, RootVarDependency>,
+	root_var_usage: &indexmap::IndexMap<Id, Vec<usize>>,
+	main_module_usage: &HashSet<Id>,
+	migratable: &std::collections::BTreeMap<usize, Vec<Id>>,
+) -> Vec<(usize, Decision)> {
+	let assignment: HashMap<&Id, usize> = migratable
+		.iter()
+		.flat_map(|(seg_idx, vars)| vars.iter().map(move |var_id| (var_id, *seg_idx)))
+		.collect();
+
+	let mut decisions = vec![];
+	for (root_var_id, segments_using) in root_var_usage.iter() {
+		let dep_info = root_dependencies.get(root_var_id);
+		for &seg_idx in segments_using {
+			let (migrated, reason) = if assignment.get(root_var_id) == Some(&seg_idx) {
+				(true, MigrationReason::SingleSegment)
+			} else if segments_using.len() > 1 {
+				(false, MigrationReason::UsedByMultipleSegments)
+			} else if main_module_usage.contains(root_var_id) {
+				(false, MigrationReason::UsedByRoot)
+			} else if dep_info.is_some_and(|d| d.is_imported) {
+				(false, MigrationReason::Imported)
+			} else if dep_info.is_some_and(|d| d.is_exported) {
+				(false, MigrationReason::Exported)
+			} else {
+				(false, MigrationReason::RootDependency)
+			};
+			decisions.push((
+				seg_idx,
+				Decision::Migration {
+					name: root_var_id.0.clone(),
+					migrated,
+					reason,
+				},
+			));
+		}
+	}
+
+	// Variables that are not used by a segment directly, but moved with one that is
+	for (seg_idx, vars) in migratable {
+		for var_id in vars {
+			let used_directly = root_var_usage
+				.get(var_id)
+				.is_some_and(|usage| usage.contains(seg_idx));
+			if !used_directly {
+				decisions.push((
+					*seg_idx,
+					Decision::Migration {
+						name: var_id.0.clone(),
+						migrated: true,
+						reason: MigrationReason::TransitiveDependency,
+					},
+				));
+			}
+		}
+	}
+	decisions
+}
+
 /// Recursively collects all transitive dependencies of a variable that should be migrated.
 fn collect_transitive_dependencies(
 	var_id: &Id,
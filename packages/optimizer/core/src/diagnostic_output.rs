@@ -0,0 +1,295 @@
+use crate::utils::{Diagnostic, DiagnosticCategory, SourceLocation};
+use crate::TransformModuleInput;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+/// The `uriBaseId` of every SARIF artifact, the diagnostic files are relative to `srcDir`
+const SRC_ROOT: &str = "SRCROOT";
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiagnosticFormat {
+	/// rustc-style messages with the highlighted source lines
+	CodeFrame,
+	/// SARIF 2.1.0, for code scanning tools
+	Sarif,
+	Json,
+}
+
+/// Renders `diagnostics`, the code frames quote the `inputs` they were reported for and SARIF
+/// artifact URIs are resolved against `src_dir`.
+pub fn render_diagnostics(
+	diagnostics: &[Diagnostic],
+	inputs: &[TransformModuleInput],
+	src_dir: &str,
+	format: DiagnosticFormat,
+) -> String {
+	match format {
+		DiagnosticFormat::CodeFrame => to_code_frames(diagnostics, inputs),
+		DiagnosticFormat::Sarif => {
+			serde_json::to_string_pretty(&to_sarif(diagnostics, inputs, src_dir))
+				.unwrap_or_default()
+		}
+		DiagnosticFormat::Json => serde_json::to_string_pretty(diagnostics).unwrap_or_default(),
+	}
+}
+
+/// One frame per diagnostic, e.g.
+///
+/// ```text
+/// error[C02]: Reference to identifier 'greet' can not be used inside a Qrl($) scope
+///  --> src/app.tsx:4:26
+///   |
+/// 4 |     return <button onClick$={() => greet()} />;
+///   |                              ^^^^^^^^^^^^^
+///   = help: Wrap greet in $()
+/// ```
+///
+/// Highlights of files missing from `inputs` only get their position.
+pub fn to_code_frames(diagnostics: &[Diagnostic], inputs: &[TransformModuleInput]) -> String {
+	let mut out = String::new();
+	for diagnostic in diagnostics {
+		write_code_frame(&mut out, diagnostic, source_of(diagnostic, inputs));
+		out.push('\n');
+	}
+	out
+}
+
+fn write_code_frame(out: &mut String, diagnostic: &Diagnostic, source: Option<&str>) {
+	let code = diagnostic
+		.code
+		.as_ref()
+		.map(|code| format!("[{}]", code))
+		.unwrap_or_default();
+	let _ = writeln!(
+		out,
+		"{}{}: {}",
+		level(&diagnostic.category),
+		code,
+		diagnostic.message
+	);
+
+	let highlights = diagnostic.highlights.as_deref().unwrap_or_default();
+	let Some(first) = highlights.first() else {
+		let _ = writeln!(out, " --> {}", diagnostic.file);
+		write_help(out, diagnostic, 1);
+		return;
+	};
+	let gutter = highlights
+		.iter()
+		.map(|h| h.start_line.to_string().len())
+		.max()
+		.unwrap_or(1);
+	let pad = " ".repeat(gutter);
+	let _ = writeln!(
+		out,
+		"{} --> {}:{}:{}",
+		" ".repeat(gutter - 1),
+		diagnostic.file,
+		first.start_line,
+		column(source, first.start_line, first.start_col)
+	);
+	if let Some(source) = source {
+		let _ = writeln!(out, "{} |", pad);
+		for highlight in highlights {
+			let Some(line) = line_of(source, highlight.start_line) else {
+				continue;
+			};
+			let _ = writeln!(out, "{:>gutter$} | {}", highlight.start_line, line);
+			let _ = writeln!(out, "{} | {}", pad, underline(line, highlight));
+		}
+	}
+	write_help(out, diagnostic, gutter);
+}
+
+fn source_of<'a>(diagnostic: &Diagnostic, inputs: &'a [TransformModuleInput]) -> Option<&'a str> {
+	inputs
+		.iter()
+		.find(|input| input.path == *diagnostic.file)
+		.map(|input| input.code.as_str())
+}
+
+fn write_help(out: &mut String, diagnostic: &Diagnostic, gutter: usize) {
+	for suggestion in diagnostic.suggestions.iter().flatten() {
+		let _ = writeln!(out, "{} = help: {}", " ".repeat(gutter), suggestion);
+	}
+}
+
+/// Carets under the highlighted columns of `line`, up to its end for multi-line highlights.
+/// Tabs before the highlight are kept so the carets line up in any tab width.
+fn underline(line: &str, highlight: &SourceLocation) -> String {
+	let start = char_col(line, highlight.start_col) - 1;
+	let end = if highlight.end_line == highlight.start_line {
+		char_col(line, highlight.end_col)
+	} else {
+		line.chars().count()
+	};
+	let indent: String = line
+		.chars()
+		.take(start)
+		.map(|c| if c == '\t' { '\t' } else { ' ' })
+		.collect();
+	format!("{}{}", indent, "^".repeat(end.saturating_sub(start).max(1)))
+}
+
+fn line_of(source: &str, line: usize) -> Option<&str> {
+	source.lines().nth(line.checked_sub(1)?)
+}
+
+/// `col` as a character column when the source is known, see `char_col`.
+fn column(source: Option<&str>, line: usize, col: usize) -> usize {
+	source
+		.and_then(|source| line_of(source, line))
+		.map_or(col, |text| char_col(text, col))
+}
+
+/// The 1-based character column of the 1-based display column `col` of `line`. swc counts
+/// tabs as 4 columns in `SourceLocation`.
+fn char_col(line: &str, col: usize) -> usize {
+	let mut width = 0;
+	for (i, c) in line.chars().enumerate() {
+		width += if c == '\t' { 4 } else { 1 };
+		if width >= col {
+			return i + 1;
+		}
+	}
+	line.chars().count().max(1)
+}
+
+const fn level(category: &DiagnosticCategory) -> &'static str {
+	match category {
+		DiagnosticCategory::Warning => "warning",
+		DiagnosticCategory::Error | DiagnosticCategory::SourceError => "error",
+	}
+}
+
+/// A SARIF 2.1.0 log with one run of the optimizer.
+///
+/// Artifact URIs are the diagnostic files, relative to the `SRCROOT` base URI, which is
+/// `src_dir` when it is absolute. Fixes become SARIF fixes with byte offset regions. Columns
+/// count code points in the `inputs` the diagnostics were reported for, or are display columns
+/// for files missing from them.
+pub fn to_sarif(
+	diagnostics: &[Diagnostic],
+	inputs: &[TransformModuleInput],
+	src_dir: &str,
+) -> serde_json::Value {
+	let rules: BTreeSet<&str> = diagnostics
+		.iter()
+		.filter_map(|diagnostic| diagnostic.code.as_deref())
+		.collect();
+	let results: Vec<_> = diagnostics
+		.iter()
+		.map(|diagnostic| sarif_result(diagnostic, source_of(diagnostic, inputs)))
+		.collect();
+	let src_root = match dir_uri(src_dir) {
+		Some(uri) => json!({ "uri": uri }),
+		None => json!({ "description": { "text": "The srcDir of the optimizer" } }),
+	};
+	json!({
+		"$schema": SARIF_SCHEMA,
+		"version": "2.1.0",
+		"runs": [{
+			"tool": {
+				"driver": {
+					"name": "qwik-optimizer",
+					"informationUri": "https://qwik.dev",
+					"rules": rules.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+				}
+			},
+			"originalUriBaseIds": { SRC_ROOT: src_root },
+			"columnKind": "unicodeCodePoints",
+			"results": results,
+		}]
+	})
+}
+
+/// `dir` as a `file:` URI ending in `/`, or `None` when it is relative. Windows paths, with or
+/// without the `\\?\` prefix of canonical paths, become `file:///C:/...`.
+fn dir_uri(dir: &str) -> Option<String> {
+	let path = dir.strip_prefix(r"\\?\").unwrap_or(dir).replace('\\', "/");
+	let is_drive =
+		path.as_bytes().get(1) == Some(&b':') && path[..1].chars().all(|c| c.is_ascii_alphabetic());
+	if !path.starts_with('/') && !is_drive {
+		return None;
+	}
+
+	let mut uri = String::from(if is_drive { "file:///" } else { "file://" });
+	for byte in path.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+				uri.push(byte as char)
+			}
+			_ => {
+				let _ = write!(uri, "%{:02X}", byte);
+			}
+		}
+	}
+	if !uri.ends_with('/') {
+		uri.push('/');
+	}
+	Some(uri)
+}
+
+fn sarif_result(diagnostic: &Diagnostic, source: Option<&str>) -> serde_json::Value {
+	let artifact = json!({ "uri": diagnostic.file, "uriBaseId": SRC_ROOT });
+	let locations: Vec<_> = diagnostic
+		.highlights
+		.iter()
+		.flatten()
+		.map(|highlight| {
+			json!({
+				"physicalLocation": {
+					"artifactLocation": artifact,
+					"region": {
+						"startLine": highlight.start_line,
+						"startColumn": column(source, highlight.start_line, highlight.start_col),
+						"endLine": highlight.end_line,
+						// SARIF end columns are exclusive
+						"endColumn": column(source, highlight.end_line, highlight.end_col) + 1,
+					}
+				}
+			})
+		})
+		.collect();
+	let locations = if locations.is_empty() {
+		vec![json!({ "physicalLocation": { "artifactLocation": artifact } })]
+	} else {
+		locations
+	};
+	let fixes: Vec<_> = diagnostic
+		.fixes
+		.iter()
+		.map(|fix| {
+			json!({
+				"description": { "text": fix.message },
+				"artifactChanges": [{
+					"artifactLocation": artifact,
+					"replacements": fix.edits.iter().map(|edit| json!({
+						"deletedRegion": {
+							"byteOffset": edit.range.start,
+							"byteLength": edit.range.end - edit.range.start,
+						},
+						"insertedContent": { "text": edit.replacement },
+					})).collect::<Vec<_>>(),
+				}]
+			})
+		})
+		.collect();
+
+	let mut result = json!({
+		"level": level(&diagnostic.category),
+		"message": { "text": diagnostic.message },
+		"locations": locations,
+	});
+	if let Some(code) = &diagnostic.code {
+		result["ruleId"] = json!(code);
+	}
+	if !fixes.is_empty() {
+		result["fixes"] = json!(fixes);
+	}
+	result
+}
@@ -1,4 +1,5 @@
 use crate::transform::{SegmentData, SegmentKind};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use swc_atoms::Atom;
 
@@ -9,7 +10,7 @@ lazy_static! {
 }
 
 // EntryStrategies
-#[derive(Debug, Serialize, Copy, Clone, Deserialize)]
+#[derive(Debug, Default, Serialize, Copy, Clone, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub enum EntryStrategy {
 	Inline,
@@ -18,6 +19,7 @@ pub enum EntryStrategy {
 	Hook,
 	Segment,
 	Component,
+	#[default]
 	Smart,
 }
 
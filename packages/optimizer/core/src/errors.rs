@@ -1,8 +1,28 @@
+use crate::utils::{Diagnostic, DiagnosticCategory, DiagnosticScope};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
 pub enum DiagnosticRule {
 	FunctionReference,
 	CanNotCapture,
 	MissingQrlImplementation,
 	PreventdefaultPassiveCheck,
+	OptimizerPanic,
+	InvalidPath,
+	CodegenFailed,
+	TransformFailed,
+	InvalidOptions,
+	InternalError,
+	UnusedSuppression,
+	RulesOfHooks,
+	JsxKey,
+	StaleCapture,
+	NonSerializableCapture,
+	A11yClickEventsHaveKeyEvents,
+	A11yNoStaticElementInteractions,
+	A11yImgAlt,
 }
 
 pub const fn get_diagnostic_code(rule: DiagnosticRule) -> &'static str {
@@ -11,5 +31,173 @@ pub const fn get_diagnostic_code(rule: DiagnosticRule) -> &'static str {
 		DiagnosticRule::CanNotCapture => "C03",
 		DiagnosticRule::MissingQrlImplementation => "C05",
 		DiagnosticRule::PreventdefaultPassiveCheck => "preventdefault-passive-check",
+		DiagnosticRule::OptimizerPanic => "optimizer-panic",
+		DiagnosticRule::InvalidPath => "invalid-path",
+		DiagnosticRule::CodegenFailed => "codegen-failed",
+		DiagnosticRule::TransformFailed => "transform-failed",
+		DiagnosticRule::InvalidOptions => "invalid-options",
+		DiagnosticRule::InternalError => "internal-error",
+		DiagnosticRule::UnusedSuppression => "unused-suppression",
+		DiagnosticRule::RulesOfHooks => "rules-of-hooks",
+		DiagnosticRule::JsxKey => "jsx-key",
+		DiagnosticRule::StaleCapture => "stale-capture",
+		DiagnosticRule::NonSerializableCapture => "non-serializable-capture",
+		DiagnosticRule::A11yClickEventsHaveKeyEvents => "a11y-click-events-have-key-events",
+		DiagnosticRule::A11yNoStaticElementInteractions => "a11y-no-static-element-interactions",
+		DiagnosticRule::A11yImgAlt => "a11y-img-alt",
+	}
+}
+
+/// Codes of the rules whose severity can be changed with the `rules` option. Optimizer failures
+/// like `internal-error` are left out, they can't be turned off.
+pub const CONFIGURABLE_RULES: &[&str] = &[
+	get_diagnostic_code(DiagnosticRule::FunctionReference),
+	get_diagnostic_code(DiagnosticRule::CanNotCapture),
+	get_diagnostic_code(DiagnosticRule::MissingQrlImplementation),
+	get_diagnostic_code(DiagnosticRule::PreventdefaultPassiveCheck),
+	get_diagnostic_code(DiagnosticRule::UnusedSuppression),
+	get_diagnostic_code(DiagnosticRule::RulesOfHooks),
+	get_diagnostic_code(DiagnosticRule::JsxKey),
+	get_diagnostic_code(DiagnosticRule::StaleCapture),
+	get_diagnostic_code(DiagnosticRule::NonSerializableCapture),
+	get_diagnostic_code(DiagnosticRule::A11yClickEventsHaveKeyEvents),
+	get_diagnostic_code(DiagnosticRule::A11yNoStaticElementInteractions),
+	get_diagnostic_code(DiagnosticRule::A11yImgAlt),
+];
+
+/// Rules that are `off` unless the `rules` option sets their severity, e.g. the accessibility
+/// checks.
+pub const OPT_IN_RULES: &[&str] = &[
+	get_diagnostic_code(DiagnosticRule::A11yClickEventsHaveKeyEvents),
+	get_diagnostic_code(DiagnosticRule::A11yNoStaticElementInteractions),
+	get_diagnostic_code(DiagnosticRule::A11yImgAlt),
+];
+
+/// What to do with the diagnostics of a rule, like eslint's `off`, `warn` and `error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleSeverity {
+	Off,
+	Warn,
+	Error,
+}
+
+/// Drops the diagnostics of rules that are `off` and sets the category of the others, rules
+/// missing from `rules` keep the category they were emitted with, or are `off` when they are
+/// in `OPT_IN_RULES`.
+pub fn apply_rule_severities(
+	diagnostics: Vec<Diagnostic>,
+	rules: &BTreeMap<String, RuleSeverity>,
+) -> Vec<Diagnostic> {
+	diagnostics
+		.into_iter()
+		.filter_map(|mut diagnostic| {
+			let severity = diagnostic.code.as_deref().and_then(|code| {
+				rules
+					.get(code)
+					.or_else(|| OPT_IN_RULES.contains(&code).then_some(&RuleSeverity::Off))
+			});
+			match severity {
+				Some(RuleSeverity::Off) => return None,
+				Some(RuleSeverity::Warn) => diagnostic.category = DiagnosticCategory::Warning,
+				Some(RuleSeverity::Error) => diagnostic.category = DiagnosticCategory::Error,
+				None => {}
+			}
+			Some(diagnostic)
+		})
+		.collect()
+}
+
+/// Why the optimizer failed. Everything but `InvalidOptions` concerns a single input, and is
+/// reported as an error diagnostic for that input instead of failing the whole call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OptimizerError {
+	/// The input path has no usable file name
+	Path { file: String, message: String },
+	/// Printing the root module or one of its segments failed
+	Codegen { file: String, message: String },
+	/// Any other failure while transforming the input, e.g. writing debug dumps
+	Transform { file: String, message: String },
+	/// The options can't be used as given
+	InvalidOptions { message: String },
+}
+
+impl OptimizerError {
+	pub fn path(file: &str, err: impl fmt::Display) -> Self {
+		Self::Path {
+			file: file.to_string(),
+			message: err.to_string(),
+		}
+	}
+
+	pub fn codegen(file: &str, err: impl fmt::Display) -> Self {
+		Self::Codegen {
+			file: file.to_string(),
+			message: err.to_string(),
+		}
+	}
+
+	pub fn transform(file: &str, err: impl fmt::Display) -> Self {
+		Self::Transform {
+			file: file.to_string(),
+			message: err.to_string(),
+		}
+	}
+
+	/// The serialized `kind` tag, e.g. `invalidOptions`, that JS bindings expose on their errors.
+	pub const fn kind(&self) -> &'static str {
+		match self {
+			Self::Path { .. } => "path",
+			Self::Codegen { .. } => "codegen",
+			Self::Transform { .. } => "transform",
+			Self::InvalidOptions { .. } => "invalidOptions",
+		}
+	}
+
+	/// The input the error is about, `None` when it's about the whole call.
+	pub fn file(&self) -> Option<&str> {
+		match self {
+			Self::Path { file, .. } | Self::Codegen { file, .. } | Self::Transform { file, .. } => {
+				Some(file)
+			}
+			Self::InvalidOptions { .. } => None,
+		}
+	}
+
+	pub fn to_diagnostic(&self) -> Diagnostic {
+		let (rule, message) = match self {
+			Self::Path { message, .. } => (DiagnosticRule::InvalidPath, message),
+			Self::Codegen { message, .. } => (DiagnosticRule::CodegenFailed, message),
+			Self::Transform { message, .. } => (DiagnosticRule::TransformFailed, message),
+			Self::InvalidOptions { message } => (DiagnosticRule::InvalidOptions, message),
+		};
+		Diagnostic {
+			category: DiagnosticCategory::Error,
+			code: Some(get_diagnostic_code(rule).to_string()),
+			file: self.file().unwrap_or_default().into(),
+			message: message.clone(),
+			highlights: None,
+			suggestions: None,
+			scope: DiagnosticScope::Optimizer,
+			fixes: vec![],
+		}
+	}
+}
+
+impl fmt::Display for OptimizerError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Path { file, message } => write!(f, "Invalid input path {}: {}", file, message),
+			Self::Codegen { file, message } => {
+				write!(f, "Generating code for {} failed: {}", file, message)
+			}
+			Self::Transform { file, message } => {
+				write!(f, "Transforming {} failed: {}", file, message)
+			}
+			Self::InvalidOptions { message } => write!(f, "Invalid options: {}", message),
+		}
 	}
 }
+
+impl std::error::Error for OptimizerError {}
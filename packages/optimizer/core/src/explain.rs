@@ -0,0 +1,194 @@
+use crate::transform::Segment;
+use crate::utils::SourceLocation;
+
+use serde::{Deserialize, Serialize};
+use swc_atoms::Atom;
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureReason {
+	/// Declared as a variable in an enclosing function scope
+	DeclaredInScope,
+	/// A parameter of the segment function itself
+	Param,
+	/// A function or class declared in an enclosing scope, which can not be captured
+	FunctionOrClass,
+	/// The segment is not a function, so its scope can not be captured
+	NotAFunction,
+	/// Imported, the segment imports it directly
+	Import,
+	/// Declared at module level, the segment imports it from the parent module
+	RootDeclaration,
+	/// Declared inside the segment, or a global
+	NotInScope,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum MigrationReason {
+	/// Only used by this segment, so the declaration moved into it
+	SingleSegment,
+	/// Moved along with another variable that depends on it
+	TransitiveDependency,
+	UsedByMultipleSegments,
+	/// Still referenced by code that stays in the root module
+	UsedByRoot,
+	Exported,
+	Imported,
+	/// Shares a declarator with, or is referenced by, a root declaration that is not moved
+	RootDependency,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum HoistReason {
+	/// No captures, the QRL is a module-level const
+	NoCaptures,
+	/// The `.w()` call stays where the handler is used
+	NotInLoop,
+	/// `component$` QRLs are never hoisted out of their scope
+	ComponentQrl,
+	/// Captures only come in through `q:p`, so the call moves to the top of the component
+	ComponentScope,
+	/// Moved to the outermost scope in which every capture is declared
+	OutermostCaptureScope,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum PropTarget {
+	Var,
+	Const,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JsxPropReason {
+	/// Only reads literals, imports and const bindings
+	ConstExpression,
+	/// Reads something that may change between renders
+	MutableExpression,
+	/// An event handler whose captures are all const
+	ConstCaptures,
+	/// An event handler that captures mutable variables
+	MutableCaptures,
+	/// Wrapped in a getter that tracks the signal or store it reads
+	Getter,
+	/// Reads a loop iteration variable
+	IterationVariable,
+	/// The element also has spread props, which are merged at runtime
+	SpreadProps,
+	/// `ref` and `q:slot` on elements are always var props
+	RefOrSlot,
+	/// Shorthand props are always var props
+	Shorthand,
+}
+
+/// One decision the optimizer made, and why.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Decision {
+	#[serde(rename_all = "camelCase")]
+	Capture {
+		name: Atom,
+		captured: bool,
+		reason: CaptureReason,
+	},
+	#[serde(rename_all = "camelCase")]
+	Migration {
+		name: Atom,
+		migrated: bool,
+		reason: MigrationReason,
+	},
+	#[serde(rename_all = "camelCase")]
+	Hoist {
+		hoisted: bool,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		current_depth: Option<usize>,
+		#[serde(default, skip_serializing_if = "Option::is_none")]
+		target_depth: Option<usize>,
+		reason: HoistReason,
+	},
+	#[serde(rename_all = "camelCase")]
+	JsxProp {
+		name: Atom,
+		target: PropTarget,
+		reason: JsxPropReason,
+		location: Option<SourceLocation>,
+	},
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentExplain {
+	pub name: Atom,
+	pub display_name: Atom,
+	pub decisions: Vec<Decision>,
+}
+
+/// Per-file trace of the optimizer decisions, collected when `explain` is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ExplainReport {
+	pub path: String,
+	/// Decisions made outside of any segment, e.g. JSX props in the root module
+	pub root: Vec<Decision>,
+	pub segments: Vec<SegmentExplain>,
+}
+
+/// Records decisions keyed by the symbol of the segment they belong to. When disabled, nothing is recorded.
+#[derive(Debug, Default)]
+pub struct Explainer {
+	decisions: Option<Vec<(Option<Atom>, Decision)>>,
+}
+
+impl Explainer {
+	pub fn new(enabled: bool) -> Self {
+		Self {
+			decisions: enabled.then(Vec::new),
+		}
+	}
+
+	pub const fn is_enabled(&self) -> bool {
+		self.decisions.is_some()
+	}
+
+	pub fn record(&mut self, segment: Option<&Atom>, decision: impl FnOnce() -> Decision) {
+		if let Some(decisions) = &mut self.decisions {
+			decisions.push((segment.cloned(), decision()));
+		}
+	}
+
+	/// Groups the decisions by segment, in the order the segments were created.
+	pub fn finish(self, path: &str, segments: &[Segment]) -> Option<ExplainReport> {
+		let decisions = self.decisions?;
+		let mut report = ExplainReport {
+			path: path.to_string(),
+			..ExplainReport::default()
+		};
+		for segment in segments {
+			report.segments.push(SegmentExplain {
+				name: segment.name.clone(),
+				display_name: segment.data.display_name.clone(),
+				decisions: vec![],
+			});
+		}
+		for (segment, decision) in decisions {
+			let Some(name) = segment else {
+				report.root.push(decision);
+				continue;
+			};
+			if let Some(entry) = report.segments.iter_mut().find(|s| s.name == name) {
+				entry.decisions.push(decision);
+			} else {
+				// Inlined segments (lib mode, inline strategies) are not part of `segments`
+				report.segments.push(SegmentExplain {
+					display_name: name.clone(),
+					name,
+					decisions: vec![decision],
+				});
+			}
+		}
+		Some(report)
+	}
+}
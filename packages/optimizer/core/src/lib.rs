@@ -9,114 +9,346 @@
 mod test;
 
 mod add_side_effect;
+mod analyze;
 mod clean_side_effects;
 mod code_move;
 mod collector;
 mod const_replace;
+mod custom_passes;
+mod debug_dump;
 mod dependency_analysis;
+mod diagnostic_output;
 mod entry_strategy;
 mod errors;
+mod explain;
 mod filter_exports;
 mod inlined_fn;
 mod is_const;
+mod module_info;
+mod options;
 mod parse;
 mod props_destructuring;
 mod rename_imports;
+mod segment_graph;
+mod size_report;
+mod stats;
 mod transform;
 mod utils;
+mod verify;
 mod words;
 
 use words::BUILDER_IO_QWIK;
 
-use anyhow::Error;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::str;
 use swc_atoms::Atom;
+use swc_common::comments::SingleThreadedComments;
+use swc_common::{sync::Lrc, SourceMap};
+use swc_ecmascript::ast;
 
-use crate::entry_strategy::parse_entry_strategy;
+pub use crate::analyze::{AnalyzeOutput, ModuleAnalysis};
+pub use crate::collector::ImportKind;
+pub use crate::custom_passes::{CustomPasses, PassContext, PassPoint};
+pub use crate::debug_dump::{DebugDumpOptions, PassDump};
+pub use crate::diagnostic_output::{
+	render_diagnostics, to_code_frames, to_sarif, DiagnosticFormat,
+};
 pub use crate::entry_strategy::EntryStrategy;
+use crate::entry_strategy::{parse_entry_strategy, EntryPolicy};
+pub use crate::errors::{OptimizerError, RuleSeverity, CONFIGURABLE_RULES, OPT_IN_RULES};
+pub use crate::explain::{
+	CaptureReason, Decision, ExplainReport, HoistReason, JsxPropReason, MigrationReason,
+	PropTarget, SegmentExplain,
+};
+pub use crate::module_info::{ModuleExport, ModuleImport, ModuleInfo, ModuleInfoOutput};
+pub use crate::options::{options_schema, TransformModulesOptionsBuilder};
 pub use crate::parse::EmitMode;
-use crate::parse::{transform_code, TransformCodeOptions};
+use crate::parse::{
+	collect_module_info, transform_code, transform_program, ParsedProgram, TransformCodeOptions,
+};
 pub use crate::parse::{
-	ErrorBuffer, MinifyMode, SegmentAnalysis, TransformModule, TransformOutput,
+	ErrorBuffer, MinifyMode, RootProgram, SegmentAnalysis, SegmentCapture, SegmentImport,
+	TransformModule, TransformOutput,
+};
+pub use crate::segment_graph::{
+	GraphEdge, GraphEdgeKind, GraphFormat, GraphNode, GraphNodeKind, SegmentGraph,
 };
-#[derive(Serialize, Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
+pub use crate::size_report::{ModuleSizeEntry, ModuleSizes, SizeReport};
+pub use crate::stats::{PassTiming, TransformStats};
+use crate::utils::catch_panic;
+pub use crate::utils::{
+	apply_fixes, Diagnostic, DiagnosticCategory, DiagnosticScope, EditRange, Fix, SourceLocation,
+	TextEdit,
+};
+#[derive(Serialize, Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct TransformModuleInput {
 	pub path: String,
+	#[serde(default)]
 	pub dev_path: Option<String>,
 	pub code: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+/// Options of the Qwik optimizer. Only `srcDir` is required, missing options take the same
+/// defaults as in `@qwik.dev/optimizer`.
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+#[schemars(title = "TransformModulesOptions")]
 pub struct TransformModulesOptions {
 	pub src_dir: String,
+	#[serde(default)]
 	pub root_dir: Option<String>,
+	#[serde(default)]
 	pub input: Vec<TransformModuleInput>,
+	#[serde(default)]
 	pub source_maps: bool,
+	#[serde(default)]
 	pub minify: MinifyMode,
+	#[serde(default)]
 	pub transpile_ts: bool,
+	#[serde(default)]
 	pub transpile_jsx: bool,
+	#[serde(default)]
 	pub preserve_filenames: bool,
+	#[serde(default)]
 	pub entry_strategy: EntryStrategy,
+	/// `entryStrategy.manual` of `@qwik.dev/optimizer`, accepted so existing callers keep working.
+	/// The optimizer doesn't use it, manual chunks are up to the bundler.
+	#[serde(default)]
+	pub manual_chunks: Option<BTreeMap<String, String>>,
+	#[serde(default)]
 	pub explicit_extensions: bool,
+	#[serde(default)]
 	pub mode: EmitMode,
+	#[serde(default)]
 	pub scope: Option<String>,
 
+	#[serde(default)]
 	pub core_module: Option<String>,
+	#[serde(default)]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub strip_exports: Option<Vec<Atom>>,
+	#[serde(default)]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub strip_ctx_name: Option<Vec<Atom>>,
+	#[serde(default)]
 	pub strip_event_handlers: bool,
+	#[serde(default)]
+	#[schemars(with = "Option<Vec<String>>")]
 	pub reg_ctx_name: Option<Vec<Atom>>,
+	#[serde(default)]
 	pub is_server: Option<bool>,
+	/// Collect per-file pass timings and counters into `TransformOutput.stats`
+	#[serde(default)]
+	pub stats: bool,
+	/// Record why segments captured, migrated and hoisted what they did into `TransformOutput.explain`
+	#[serde(default)]
+	pub explain: bool,
+	/// Measure the raw, gzip and brotli size of every emitted module into `TransformOutput.size_report`
+	#[serde(default)]
+	pub size_report: bool,
+	/// Capture the code after every pass into `TransformOutput.debug_dumps`
+	#[serde(default)]
+	pub debug_dump: Option<DebugDumpOptions>,
+	/// Re-parse every emitted module and report broken ones as `internal-error` diagnostics
+	#[serde(default)]
+	pub verify: bool,
+	/// Severity overrides keyed by diagnostic code, e.g. `{ "C05": "warn" }`
+	#[serde(default)]
+	pub rules: BTreeMap<String, RuleSeverity>,
+	/// Extra passes registered by Rust embedders, not available from JS
+	#[serde(skip)]
+	pub custom_passes: CustomPasses,
+}
+
+pub fn transform_modules(
+	config: TransformModulesOptions,
+) -> Result<TransformOutput, OptimizerError> {
+	transform_programs(config, vec![])
 }
 
-pub fn transform_modules(config: TransformModulesOptions) -> Result<TransformOutput, Error> {
+/// A module that was already parsed by an swc-based host.
+pub struct TransformProgramInput {
+	pub path: String,
+	pub dev_path: Option<String>,
+	pub program: ast::Program,
+	pub source_map: Lrc<SourceMap>,
+	pub comments: SingleThreadedComments,
+}
+
+/// Like `transform_modules`, but also transforms already parsed `programs` without printing and
+/// re-parsing them. The programs are transformed after `config.input`.
+pub fn transform_programs(
+	config: TransformModulesOptions,
+	programs: Vec<TransformProgramInput>,
+) -> Result<TransformOutput, OptimizerError> {
+	config.validate()?;
 	let core_module = config
 		.core_module
-		.map_or_else(|| BUILDER_IO_QWIK.clone(), |s| s.into());
-	let src_dir = std::path::Path::new(&config.src_dir);
-	let root_dir = config.root_dir.as_ref().map(Path::new);
-
+		.as_ref()
+		.map_or_else(|| BUILDER_IO_QWIK.clone(), |s| s.as_str().into());
 	let entry_policy = &*parse_entry_strategy(&config.entry_strategy);
 
-	let iterator = config.input.iter();
-
-	let iterator = iterator.map(|input| -> Result<TransformOutput, Error> {
-		transform_code(TransformCodeOptions {
-			src_dir,
-			root_dir,
-			relative_path: &input.path,
-			dev_path: input.dev_path.as_deref(),
-			code: &input.code,
-			minify: config.minify,
-			source_maps: config.source_maps,
-			transpile_ts: config.transpile_ts,
-			transpile_jsx: config.transpile_jsx,
-			preserve_filenames: config.preserve_filenames,
-			explicit_extensions: config.explicit_extensions,
+	let iterator = config.input.iter().map(|input| {
+		isolated(&input.path, || {
+			transform_code(code_options(
+				&config,
+				&core_module,
+				entry_policy,
+				&input.path,
+				input.dev_path.as_deref(),
+				&input.code,
+			))
+		})
+	});
+	let parsed_iterator = programs.into_iter().map(|input| {
+		let options = code_options(
+			&config,
+			&core_module,
 			entry_policy,
-			mode: config.mode,
-			scope: config.scope.as_ref(),
-			core_module: core_module.clone(),
-			entry_strategy: config.entry_strategy,
-			reg_ctx_name: config.reg_ctx_name.as_deref(),
-			strip_exports: config.strip_exports.as_deref(),
-			strip_ctx_name: config.strip_ctx_name.as_deref(),
-			strip_event_handlers: config.strip_event_handlers,
-			// If you don't specify is_server, the safe value is true
-			is_server: config.is_server.unwrap_or(true),
+			&input.path,
+			input.dev_path.as_deref(),
+			"",
+		);
+		isolated(&input.path, || {
+			transform_program(
+				options,
+				ParsedProgram {
+					program: input.program,
+					source_map: input.source_map,
+					comments: input.comments,
+				},
+			)
 		})
 	});
 
-	#[allow(clippy::manual_try_fold)]
-	let final_output: Result<TransformOutput, Error> =
-		iterator.fold(Ok(TransformOutput::new()), |x, y| Ok(x?.append(&mut y?)));
-
-	let mut final_output = final_output?;
+	let mut final_output = iterator
+		.chain(parsed_iterator)
+		.fold(TransformOutput::new(), |x, mut y| x.append(&mut y));
 	final_output.modules.sort_unstable_by_key(|key| key.order);
+	if config.size_report {
+		final_output.size_report = Some(final_output.get_size_report()?);
+	}
 
 	Ok(final_output)
 }
+
+/// Runs `transform_modules` only up to segmentation and returns the segment metadata, imports,
+/// exports and diagnostics of every input. Segment modules are not built and no code is emitted.
+pub fn analyze_modules(config: TransformModulesOptions) -> Result<AnalyzeOutput, OptimizerError> {
+	config.validate()?;
+	let core_module = config
+		.core_module
+		.as_ref()
+		.map_or_else(|| BUILDER_IO_QWIK.clone(), |s| s.as_str().into());
+	let entry_policy = &*parse_entry_strategy(&config.entry_strategy);
+
+	let output = config
+		.input
+		.iter()
+		.map(|input| {
+			isolated(&input.path, || {
+				transform_code(TransformCodeOptions {
+					analyze_only: true,
+					..code_options(
+						&config,
+						&core_module,
+						entry_policy,
+						&input.path,
+						input.dev_path.as_deref(),
+						&input.code,
+					)
+				})
+			})
+		})
+		.fold(TransformOutput::new(), |x, mut y| x.append(&mut y));
+
+	Ok(AnalyzeOutput {
+		modules: output.analysis,
+		diagnostics: output.diagnostics,
+		is_type_script: output.is_type_script,
+		is_jsx: output.is_jsx,
+		stats: output.stats,
+	})
+}
+
+/// Parses every input and returns its imports, dynamic imports, exports and root declarations.
+/// Only `src_dir` and `input` are used, nothing is transformed.
+pub fn module_info(config: TransformModulesOptions) -> Result<ModuleInfoOutput, OptimizerError> {
+	config.validate()?;
+	let src_dir = Path::new(&config.src_dir);
+	Ok(config
+		.input
+		.iter()
+		.map(|input| {
+			catch_panic(&input.path, || {
+				collect_module_info(src_dir, &input.path, &input.code)
+					.map_err(|err| Box::new(err.to_diagnostic()))
+			})
+			.and_then(|output| output)
+			.unwrap_or_else(|diagnostic| ModuleInfoOutput {
+				modules: vec![],
+				diagnostics: vec![*diagnostic],
+			})
+		})
+		.fold(ModuleInfoOutput::default(), |x, mut y| x.append(&mut y)))
+}
+
+/// Transforms one input. Errors and panics only fail that input, with an error diagnostic.
+fn isolated(
+	path: &str,
+	transform: impl FnOnce() -> Result<TransformOutput, OptimizerError>,
+) -> TransformOutput {
+	catch_panic(path, || {
+		transform().map_err(|err| Box::new(err.to_diagnostic()))
+	})
+	.and_then(|output| output)
+	.unwrap_or_else(|diagnostic| TransformOutput {
+		diagnostics: vec![*diagnostic],
+		..TransformOutput::new()
+	})
+}
+
+fn code_options<'a>(
+	config: &'a TransformModulesOptions,
+	core_module: &Atom,
+	entry_policy: &'a dyn EntryPolicy,
+	path: &'a str,
+	dev_path: Option<&'a str>,
+	code: &'a str,
+) -> TransformCodeOptions<'a> {
+	TransformCodeOptions {
+		src_dir: Path::new(&config.src_dir),
+		root_dir: config.root_dir.as_ref().map(Path::new),
+		relative_path: path,
+		dev_path,
+		code,
+		minify: config.minify,
+		source_maps: config.source_maps,
+		transpile_ts: config.transpile_ts,
+		transpile_jsx: config.transpile_jsx,
+		preserve_filenames: config.preserve_filenames,
+		explicit_extensions: config.explicit_extensions,
+		entry_policy,
+		mode: config.mode,
+		scope: config.scope.as_ref(),
+		core_module: core_module.clone(),
+		entry_strategy: config.entry_strategy,
+		reg_ctx_name: config.reg_ctx_name.as_deref(),
+		strip_exports: config.strip_exports.as_deref(),
+		strip_ctx_name: config.strip_ctx_name.as_deref(),
+		strip_event_handlers: config.strip_event_handlers,
+		// If you don't specify is_server, the safe value is true
+		is_server: config.is_server.unwrap_or(true),
+		stats: config.stats,
+		explain: config.explain,
+		debug_dump: config.debug_dump.as_ref(),
+		custom_passes: &config.custom_passes,
+		verify: config.verify,
+		rules: &config.rules,
+		analyze_only: false,
+	}
+}
@@ -0,0 +1,121 @@
+use crate::collector::{GlobalCollect, ImportKind};
+use crate::utils::Diagnostic;
+use serde::{Deserialize, Serialize};
+use swc_atoms::{atom, Atom};
+use swc_ecmascript::ast;
+
+/// The imports and exports of a module, as `GlobalCollect` sees them before the transform.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleInfo {
+	/// Path of the input file, relative to `src_dir`
+	pub path: String,
+	pub imports: Vec<ModuleImport>,
+	/// Sources of `import('...')` calls with a string literal argument
+	pub dynamic_imports: Vec<Atom>,
+	pub exports: Vec<ModuleExport>,
+	/// Sources of `export ... from '...'`, which `exports` doesn't list
+	pub reexports: Vec<Atom>,
+	/// Names declared at the top level without `export`, exported ones are in `exports`
+	pub root_declarations: Vec<Atom>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleImport {
+	/// The local binding
+	pub local: Atom,
+	pub source: Atom,
+	/// The imported name, `default` or `*` for namespace imports
+	pub specifier: Atom,
+	pub kind: ImportKind,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleExport {
+	/// The local binding, `None` for `export default <expression>`
+	pub local: Option<Atom>,
+	/// The names the binding is exported as
+	pub exported: Vec<Atom>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleInfoOutput {
+	pub modules: Vec<ModuleInfo>,
+	pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ModuleInfo {
+	pub fn new(path: &str, collect: &GlobalCollect, program: &ast::Program) -> Self {
+		let imports = collect
+			.imports
+			.iter()
+			.filter(|(_, import)| !import.synthetic)
+			.map(|(local, import)| ModuleImport {
+				local: local.0.clone(),
+				source: import.source.clone(),
+				specifier: import.specifier.clone(),
+				kind: import.kind,
+			})
+			.collect();
+		let mut exports: Vec<_> = collect
+			.exports
+			.values()
+			.map(|info| ModuleExport {
+				local: Some(info.local_id.0.clone()),
+				exported: info
+					.exported_names
+					.iter()
+					.map(|name| name.clone().unwrap_or_else(|| info.local_id.0.clone()))
+					.collect(),
+			})
+			.collect();
+
+		let mut reexports = vec![];
+		if let ast::Program::Module(module) = program {
+			for item in &module.body {
+				match item {
+					ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportDefaultExpr(_)) => {
+						exports.push(ModuleExport {
+							local: None,
+							exported: vec![atom!("default")],
+						});
+					}
+					ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportAll(export)) => {
+						reexports.push(export.src.value.clone());
+					}
+					ast::ModuleItem::ModuleDecl(ast::ModuleDecl::ExportNamed(
+						ast::NamedExport { src: Some(src), .. },
+					)) => {
+						reexports.push(src.value.clone());
+					}
+					_ => {}
+				}
+			}
+		}
+
+		Self {
+			path: path.to_string(),
+			imports,
+			dynamic_imports: collect.dynamic_imports.clone(),
+			exports,
+			reexports,
+			root_declarations: collect
+				.root
+				.keys()
+				.filter(|id| !collect.exports.values().any(|info| info.local_id == **id))
+				.map(|id| id.0.clone())
+				.collect(),
+		}
+	}
+}
+
+impl ModuleInfoOutput {
+	pub fn append(mut self, output: &mut Self) -> Self {
+		self.modules.append(&mut output.modules);
+		self.diagnostics.append(&mut output.diagnostics);
+		self
+	}
+}
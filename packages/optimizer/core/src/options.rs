@@ -0,0 +1,235 @@
+use crate::custom_passes::CustomPasses;
+use crate::debug_dump::DebugDumpOptions;
+use crate::entry_strategy::EntryStrategy;
+use crate::errors::{OptimizerError, RuleSeverity, CONFIGURABLE_RULES};
+use crate::parse::{EmitMode, MinifyMode};
+use crate::{TransformModuleInput, TransformModulesOptions};
+use std::collections::HashSet;
+use swc_atoms::Atom;
+
+impl TransformModulesOptions {
+	pub fn builder(src_dir: impl Into<String>) -> TransformModulesOptionsBuilder {
+		TransformModulesOptionsBuilder {
+			options: Self {
+				src_dir: src_dir.into(),
+				..Self::default()
+			},
+		}
+	}
+
+	/// Rejects options that can't work for any input. Every problem is listed in the message.
+	pub fn validate(&self) -> Result<(), OptimizerError> {
+		let mut problems = vec![];
+		if self.src_dir.is_empty() {
+			problems.push("srcDir must not be empty".to_string());
+		}
+		if self.core_module.as_deref() == Some("") {
+			problems.push("coreModule must not be empty".to_string());
+		}
+		if self.mode == EmitMode::Lib {
+			// A library is consumed by both the server and the client build, stripping anything
+			// from it breaks one of them.
+			if self.strip_exports.as_ref().is_some_and(|s| !s.is_empty()) {
+				problems.push("stripExports can't be used in lib mode".to_string());
+			}
+			if self.strip_ctx_name.as_ref().is_some_and(|s| !s.is_empty()) {
+				problems.push("stripCtxName can't be used in lib mode".to_string());
+			}
+			if self.strip_event_handlers {
+				problems.push("stripEventHandlers can't be used in lib mode".to_string());
+			}
+		}
+		if let Some(DebugDumpOptions { ast, out_dir }) = &self.debug_dump {
+			if *ast && cfg!(not(feature = "ast-dump")) {
+				problems.push(
+					"debugDump.ast needs an optimizer built with the ast-dump feature".to_string(),
+				);
+			}
+			match out_dir.as_deref() {
+				Some(_) if cfg!(all(target_arch = "wasm32", target_os = "unknown")) => {
+					problems.push(
+						"debugDump.outDir is not supported by the wasm optimizer, read TransformOutput.debugDumps instead"
+							.to_string(),
+					);
+				}
+				Some("") => problems.push("debugDump.outDir must not be empty".to_string()),
+				_ => {}
+			}
+		}
+		for code in self.rules.keys() {
+			if !CONFIGURABLE_RULES.contains(&code.as_str()) {
+				problems.push(format!("unknown rule {} in rules", code));
+			}
+		}
+		let mut paths = HashSet::new();
+		for input in &self.input {
+			if !paths.insert(input.path.as_str()) {
+				problems.push(format!("input {} is listed more than once", input.path));
+			}
+		}
+
+		if problems.is_empty() {
+			Ok(())
+		} else {
+			Err(OptimizerError::InvalidOptions {
+				message: problems.join(", "),
+			})
+		}
+	}
+}
+
+/// JSON Schema of `TransformModulesOptions`, for editor validation of config files.
+pub fn options_schema() -> serde_json::Value {
+	schemars::schema_for!(TransformModulesOptions).to_value()
+}
+
+/// Builds `TransformModulesOptions` from the defaults, `build` validates them.
+#[derive(Debug)]
+pub struct TransformModulesOptionsBuilder {
+	options: TransformModulesOptions,
+}
+
+impl TransformModulesOptionsBuilder {
+	pub fn input(mut self, path: impl Into<String>, code: impl Into<String>) -> Self {
+		self.options.input.push(TransformModuleInput {
+			path: path.into(),
+			dev_path: None,
+			code: code.into(),
+		});
+		self
+	}
+
+	pub fn dev_input(
+		mut self,
+		path: impl Into<String>,
+		dev_path: impl Into<String>,
+		code: impl Into<String>,
+	) -> Self {
+		self.options.input.push(TransformModuleInput {
+			path: path.into(),
+			dev_path: Some(dev_path.into()),
+			code: code.into(),
+		});
+		self
+	}
+
+	pub fn root_dir(mut self, root_dir: impl Into<String>) -> Self {
+		self.options.root_dir = Some(root_dir.into());
+		self
+	}
+
+	pub const fn source_maps(mut self, source_maps: bool) -> Self {
+		self.options.source_maps = source_maps;
+		self
+	}
+
+	pub const fn minify(mut self, minify: MinifyMode) -> Self {
+		self.options.minify = minify;
+		self
+	}
+
+	pub const fn transpile_ts(mut self, transpile_ts: bool) -> Self {
+		self.options.transpile_ts = transpile_ts;
+		self
+	}
+
+	pub const fn transpile_jsx(mut self, transpile_jsx: bool) -> Self {
+		self.options.transpile_jsx = transpile_jsx;
+		self
+	}
+
+	pub const fn preserve_filenames(mut self, preserve_filenames: bool) -> Self {
+		self.options.preserve_filenames = preserve_filenames;
+		self
+	}
+
+	pub const fn entry_strategy(mut self, entry_strategy: EntryStrategy) -> Self {
+		self.options.entry_strategy = entry_strategy;
+		self
+	}
+
+	pub const fn explicit_extensions(mut self, explicit_extensions: bool) -> Self {
+		self.options.explicit_extensions = explicit_extensions;
+		self
+	}
+
+	pub const fn mode(mut self, mode: EmitMode) -> Self {
+		self.options.mode = mode;
+		self
+	}
+
+	pub fn scope(mut self, scope: impl Into<String>) -> Self {
+		self.options.scope = Some(scope.into());
+		self
+	}
+
+	pub fn core_module(mut self, core_module: impl Into<String>) -> Self {
+		self.options.core_module = Some(core_module.into());
+		self
+	}
+
+	pub fn strip_exports(mut self, names: impl IntoIterator<Item = impl Into<Atom>>) -> Self {
+		self.options.strip_exports = Some(names.into_iter().map(Into::into).collect());
+		self
+	}
+
+	pub fn strip_ctx_name(mut self, names: impl IntoIterator<Item = impl Into<Atom>>) -> Self {
+		self.options.strip_ctx_name = Some(names.into_iter().map(Into::into).collect());
+		self
+	}
+
+	pub const fn strip_event_handlers(mut self, strip_event_handlers: bool) -> Self {
+		self.options.strip_event_handlers = strip_event_handlers;
+		self
+	}
+
+	pub fn reg_ctx_name(mut self, names: impl IntoIterator<Item = impl Into<Atom>>) -> Self {
+		self.options.reg_ctx_name = Some(names.into_iter().map(Into::into).collect());
+		self
+	}
+
+	pub const fn is_server(mut self, is_server: bool) -> Self {
+		self.options.is_server = Some(is_server);
+		self
+	}
+
+	pub const fn stats(mut self, stats: bool) -> Self {
+		self.options.stats = stats;
+		self
+	}
+
+	pub const fn explain(mut self, explain: bool) -> Self {
+		self.options.explain = explain;
+		self
+	}
+
+	pub const fn size_report(mut self, size_report: bool) -> Self {
+		self.options.size_report = size_report;
+		self
+	}
+
+	pub fn debug_dump(mut self, debug_dump: DebugDumpOptions) -> Self {
+		self.options.debug_dump = Some(debug_dump);
+		self
+	}
+
+	pub const fn verify(mut self, verify: bool) -> Self {
+		self.options.verify = verify;
+		self
+	}
+
+	pub fn rule(mut self, code: impl Into<String>, severity: RuleSeverity) -> Self {
+		self.options.rules.insert(code.into(), severity);
+		self
+	}
+
+	pub fn custom_passes(mut self, custom_passes: CustomPasses) -> Self {
+		self.options.custom_passes = custom_passes;
+		self
+	}
+
+	pub fn build(self) -> Result<TransformModulesOptions, OptimizerError> {
+		self.options.validate()?;
+		Ok(self.options)
+	}
+}
@@ -1,39 +1,56 @@
+use std::cell::RefCell;
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::OsStr;
 use std::hash::Hasher;
 use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
 use std::str;
 
 use crate::add_side_effect::SideEffectVisitor;
+use crate::analyze::{export_names, ModuleAnalysis};
 use crate::clean_side_effects::Treeshaker;
 use crate::code_move::{new_module, NewModuleCtx};
-use crate::collector::{collect_from_pat, global_collect, Id, Import, ImportKind};
+use crate::collector::{collect_from_pat, global_collect, GlobalCollect, Id, Import, ImportKind};
 use crate::const_replace::ConstReplacerVisitor;
+use crate::custom_passes::{CustomPasses, PassContext, PassPoint};
+use crate::debug_dump::{DebugDumpOptions, PassDump, PassDumper};
 use crate::dependency_analysis::{
 	analyze_root_dependencies, build_main_module_usage_set, build_root_var_usage_map,
-	find_migratable_vars, RootVarDecl, RootVarDependency,
+	explain_migration, find_migratable_vars, RootVarDecl, RootVarDependency,
 };
 use crate::entry_strategy::EntryPolicy;
+use crate::errors::{apply_rule_severities, OptimizerError, RuleSeverity};
+use crate::explain::{ExplainReport, Explainer};
 use crate::filter_exports::StripExportsVisitor;
+use crate::module_info::{ModuleInfo, ModuleInfoOutput};
 use crate::props_destructuring::transform_props_destructuring;
 use crate::rename_imports::RenameTransform;
+use crate::size_report::SizeReport;
+use crate::stats::{count_import_specifiers, PassTimer, TransformStats};
 use crate::transform::{
-	create_synthetic_named_export, QwikTransform, QwikTransformOptions, Segment, SegmentKind,
+	collect_jsx_attr_spans, create_synthetic_named_export, IdentType, QwikTransform,
+	QwikTransformOptions, Segment, SegmentKind,
 };
-use crate::utils::{Diagnostic, DiagnosticCategory, DiagnosticScope, SourceLocation};
+use crate::utils::{
+	Diagnostic, DiagnosticCategory, DiagnosticScope, EditRange, Fix, SourceLocation, TextEdit,
+};
+use crate::verify::{verify_modules, VerifySource};
 use crate::words::{_INLINED_QRL_DEV, _NOOP_QRL_DEV, _QRL_DEV};
 use crate::EntryStrategy;
 use indexmap::IndexMap;
 use path_slash::PathExt;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use swc_common::{Span, DUMMY_SP};
 
-use anyhow::{Context, Error};
+use anyhow::Error;
 
 use swc_atoms::Atom;
-use swc_common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
-use swc_common::errors::{DiagnosticBuilder, DiagnosticId, Emitter, Handler, Level};
+use swc_common::comments::{
+	Comment, CommentKind, Comments, SingleThreadedComments, SingleThreadedCommentsMapInner,
+};
+use swc_common::errors::{Applicability, DiagnosticBuilder, DiagnosticId, Emitter, Handler, Level};
 use swc_common::{sync::Lrc, FileName, Globals, Mark, SourceMap};
 use swc_ecmascript::ast;
 use swc_ecmascript::codegen::text_writer::JsWriter;
@@ -60,23 +77,53 @@ pub struct SegmentAnalysis {
 	pub ctx_name: Atom,
 	pub captures: bool,
 	pub loc: (u32, u32),
+	/// Line/column range of the segment in the original source
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub location: Option<SourceLocation>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub param_names: Option<Vec<Atom>>,
 	#[serde(skip_serializing_if = "Option::is_none")]
 	pub capture_names: Option<Vec<Atom>>,
+	/// The captured identifiers together with how they were declared
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub captured: Vec<SegmentCapture>,
+	/// The imports the segment code depends on, including imports from its origin module
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub imports: Vec<SegmentImport>,
+	/// Byte length of the emitted segment code
+	pub size: usize,
+	/// All enclosing segments, outermost first. The last entry is `parent`.
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub parents: Vec<Atom>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentCapture {
+	pub name: Atom,
+	pub kind: IdentType,
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentImport {
+	pub source: Atom,
+	pub specifier: Atom,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum MinifyMode {
+	#[default]
 	Simplify,
 	None,
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, JsonSchema, Copy, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub enum EmitMode {
 	Prod,
+	#[default]
 	Lib,
 	Dev,
 	Test,
@@ -106,6 +153,14 @@ pub struct TransformCodeOptions<'a> {
 	pub strip_ctx_name: Option<&'a [Atom]>,
 	pub strip_event_handlers: bool,
 	pub is_server: bool,
+	pub stats: bool,
+	pub explain: bool,
+	pub debug_dump: Option<&'a DebugDumpOptions>,
+	pub custom_passes: &'a CustomPasses,
+	pub verify: bool,
+	pub rules: &'a BTreeMap<String, RuleSeverity>,
+	/// Stop after segmentation and fill `TransformOutput.analysis` instead of `modules`
+	pub analyze_only: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -115,12 +170,28 @@ pub struct TransformOutput {
 	pub diagnostics: Vec<Diagnostic>,
 	pub is_type_script: bool,
 	pub is_jsx: bool,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub stats: Vec<TransformStats>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub explain: Vec<ExplainReport>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub debug_dumps: Vec<PassDump>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub size_report: Option<SizeReport>,
+	/// Only filled by `analyze_only` runs, `analyze_modules` returns it as `AnalyzeOutput.modules`
+	#[serde(skip)]
+	pub analysis: Vec<ModuleAnalysis>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct QwikBundle {
 	pub size: usize,
+	/// Only present when the `size_report` option is enabled
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub gzip_size: Option<usize>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub brotli_size: Option<usize>,
 	pub symbols: Vec<Atom>,
 }
 
@@ -141,6 +212,10 @@ impl TransformOutput {
 	pub fn append(mut self, output: &mut Self) -> Self {
 		self.modules.append(&mut output.modules);
 		self.diagnostics.append(&mut output.diagnostics);
+		self.stats.append(&mut output.stats);
+		self.explain.append(&mut output.explain);
+		self.debug_dumps.append(&mut output.debug_dumps);
+		self.analysis.append(&mut output.analysis);
 		self.is_type_script = self.is_type_script || output.is_type_script;
 		self.is_jsx = self.is_jsx || output.is_jsx;
 		self
@@ -165,11 +240,20 @@ impl TransformOutput {
 				manifest
 					.symbols
 					.insert(segment.name.clone(), segment.clone());
+				let sizes = self.size_report.as_ref().and_then(|report| {
+					report
+						.modules
+						.iter()
+						.find(|entry| entry.path == module.path)
+						.map(|entry| entry.sizes)
+				});
 				manifest.bundles.insert(
 					filename.clone(),
 					QwikBundle {
 						symbols: vec![segment.name.clone()],
 						size: module.code.len(),
+						gzip_size: sizes.map(|sizes| sizes.gzip),
+						brotli_size: sizes.map(|sizes| sizes.brotli),
 					},
 				);
 			}
@@ -194,6 +278,20 @@ pub struct TransformModule {
 
 	#[serde(skip_serializing)]
 	pub order: u64,
+
+	/// The transformed root AST, only kept for inputs given as a `ParsedProgram`
+	#[serde(skip)]
+	pub program: Option<RootProgram>,
+}
+
+/// The root module handed back to a host that passed in a `ParsedProgram`, so it can keep
+/// working on the AST instead of re-parsing `TransformModule.code`.
+#[derive(Debug)]
+pub struct RootProgram {
+	pub program: ast::Program,
+	/// Comments by position, including the ones the optimizer added, e.g. `#__PURE__` annotations
+	pub leading_comments: SingleThreadedCommentsMapInner,
+	pub trailing_comments: SingleThreadedCommentsMapInner,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -205,185 +303,407 @@ impl Emitter for ErrorBuffer {
 	}
 }
 
-pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, anyhow::Error> {
+/// A module the host already parsed, e.g. a Rust bundler or an swc plugin.
+pub struct ParsedProgram {
+	pub program: ast::Program,
+	pub source_map: Lrc<SourceMap>,
+	pub comments: SingleThreadedComments,
+}
+
+pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, OptimizerError> {
 	let source_map = Lrc::new(SourceMap::default());
 	let path_data = parse_path(
 		config.relative_path.replace('\\', "/").as_str(),
 		config.src_dir,
 	)?;
-	let result = parse(
-		config.code,
-		&path_data,
-		config.root_dir,
-		Lrc::clone(&source_map),
-	);
-	// dbg!(&module);
+	let mut timer = PassTimer::new(config.stats, config.relative_path);
+	let result: PResult<(ast::Program, SingleThreadedComments)> = timer.time("parse", || {
+		parse(
+			config.code,
+			&path_data,
+			config.root_dir,
+			Lrc::clone(&source_map),
+		)
+	});
+
+	match result {
+		Ok((program, comments)) => swc_common::GLOBALS.set(&Globals::new(), || {
+			let parsed = ParsedProgram {
+				program,
+				source_map,
+				comments,
+			};
+			transform_parsed(config, path_data, parsed, timer, false)
+		}),
+		Err(err) => {
+			let error_buffer = ErrorBuffer::default();
+			let handler = Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
+			err.into_diagnostic(&handler).emit();
+			let origin: Atom = Atom::from(path_data.rel_path.to_string_lossy());
+			let diagnostics = handle_error(&error_buffer, origin, &source_map);
+			Ok(TransformOutput {
+				modules: vec![],
+				diagnostics,
+				is_type_script: false,
+				is_jsx: false,
+				stats: timer.finish().into_iter().collect(),
+				explain: vec![],
+				debug_dumps: vec![],
+				size_report: None,
+				analysis: vec![],
+			})
+		}
+	}
+}
+
+/// Parses `code` and collects its imports and exports with `GlobalCollect`, without transforming it.
+pub fn collect_module_info(
+	src_dir: &Path,
+	relative_path: &str,
+	code: &str,
+) -> Result<ModuleInfoOutput, OptimizerError> {
+	let source_map = Lrc::new(SourceMap::default());
+	let path_data = parse_path(relative_path.replace('\\', "/").as_str(), src_dir)?;
+	swc_common::GLOBALS.set(&Globals::new(), || {
+		match parse(code, &path_data, None, Lrc::clone(&source_map)) {
+			Ok((mut program, _)) => {
+				let (is_type_script, _) = parse_filename(&path_data);
+				program.visit_mut_with(&mut resolver(Mark::new(), Mark::new(), is_type_script));
+				let collect = global_collect(&program);
+				Ok(ModuleInfoOutput {
+					modules: vec![ModuleInfo::new(relative_path, &collect, &program)],
+					diagnostics: vec![],
+				})
+			}
+			Err(err) => {
+				let error_buffer = ErrorBuffer::default();
+				let handler = Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
+				err.into_diagnostic(&handler).emit();
+				let origin: Atom = Atom::from(path_data.rel_path.to_string_lossy());
+				Ok(ModuleInfoOutput {
+					modules: vec![],
+					diagnostics: handle_error(&error_buffer, origin, &source_map),
+				})
+			}
+		}
+	})
+}
+
+/// Runs the optimizer on a program the caller already parsed, skipping the parse step of
+/// `transform_code` (`config.code` is ignored). The program must not have been through swc's
+/// `resolver` yet. swc `GLOBALS` that are already set are reused, a fresh set is used otherwise.
+/// Besides its code, the root module comes back as `TransformModule.program`.
+pub fn transform_program(
+	config: TransformCodeOptions,
+	parsed: ParsedProgram,
+) -> Result<TransformOutput, OptimizerError> {
+	let path_data = parse_path(
+		config.relative_path.replace('\\', "/").as_str(),
+		config.src_dir,
+	)?;
+	let timer = PassTimer::new(config.stats, config.relative_path);
+	if swc_common::GLOBALS.is_set() {
+		transform_parsed(config, path_data, parsed, timer, true)
+	} else {
+		swc_common::GLOBALS.set(&Globals::new(), || {
+			transform_parsed(config, path_data, parsed, timer, true)
+		})
+	}
+}
+
+fn transform_parsed(
+	config: TransformCodeOptions,
+	path_data: PathData,
+	parsed: ParsedProgram,
+	mut timer: PassTimer,
+	keep_program: bool,
+) -> Result<TransformOutput, OptimizerError> {
+	let ParsedProgram {
+		program,
+		source_map,
+		comments,
+	} = parsed;
+	let (is_type_script, is_jsx) = parse_filename(&path_data);
 	let transpile_jsx = config.transpile_jsx;
 	let transpile_ts = config.transpile_ts;
 
 	let origin: Atom = Atom::from(path_data.rel_path.to_string_lossy());
 
-	match result {
-		Ok((program, comments, is_type_script, is_jsx)) => {
-			let extension = match (transpile_ts, transpile_jsx, is_type_script, is_jsx) {
-				(true, true, _, _) => Atom::from("js"),
-				(true, false, _, true) => Atom::from("jsx"),
-				(true, false, _, false) => Atom::from("js"),
-				(false, true, true, _) => Atom::from("ts"),
-				(false, true, false, _) => Atom::from("js"),
-				(false, false, _, _) => Atom::from(path_data.extension.clone()),
-			};
-			let error_buffer = ErrorBuffer::default();
-			let handler = swc_common::errors::Handler::with_emitter(
-				true,
-				false,
-				Box::new(error_buffer.clone()),
-			);
-
-			swc_common::GLOBALS.set(&Globals::new(), || {
-				swc_common::errors::HANDLER.set(&handler, || {
-					let unresolved_mark = Mark::new();
-					let top_level_mark = Mark::new();
-
-					let mut program = program;
-
-					if let Some(strip_exports) = config.strip_exports {
-						let mut visitor = StripExportsVisitor::new(strip_exports);
-						program.visit_mut_with(&mut visitor);
-					}
+	let extension = match (transpile_ts, transpile_jsx, is_type_script, is_jsx) {
+		(true, true, _, _) => Atom::from("js"),
+		(true, false, _, true) => Atom::from("jsx"),
+		(true, false, _, false) => Atom::from("js"),
+		(false, true, true, _) => Atom::from("ts"),
+		(false, true, false, _) => Atom::from("js"),
+		(false, false, _, _) => Atom::from(path_data.extension.clone()),
+	};
+	let error_buffer = ErrorBuffer::default();
+	let handler =
+		swc_common::errors::Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
+
+	swc_common::errors::HANDLER.set(&handler, || {
+		let unresolved_mark = Mark::new();
+		let top_level_mark = Mark::new();
+
+		let mut program = program;
+		let mut dumper = PassDumper::new(
+			config.debug_dump,
+			config.relative_path,
+			Lrc::clone(&source_map),
+		);
+		dumper.dump("parse", &program, &comments);
 
-					let mut did_transform = false;
+		if let Some(strip_exports) = config.strip_exports {
+			let mut visitor = StripExportsVisitor::new(strip_exports);
+			timer.time("strip_exports", || program.visit_mut_with(&mut visitor));
+			dumper.dump("strip_exports", &program, &comments);
+		}
 
-					if transpile_ts && is_type_script {
-						did_transform = true;
-						program.mutate(&mut typescript::strip(Default::default(), top_level_mark));
-					}
+		let pass_ctx = PassContext {
+			path: config.relative_path,
+			segment: None,
+			unresolved_mark,
+			top_level_mark,
+		};
+		run_custom_passes(
+			config.custom_passes,
+			PassPoint::BeforeTypescript,
+			&mut program,
+			&pass_ctx,
+			&mut timer,
+			&mut dumper,
+			&comments,
+		);
 
-					if transpile_jsx && is_jsx {
-						did_transform = true;
-						let mut react_options = react::Options::default();
-						if is_jsx {
-							react_options.next = Some(true);
-							react_options.throw_if_namespace = Some(false);
-							react_options.runtime = Some(react::Runtime::Automatic);
-							react_options.import_source = Some("@qwik.dev/core".to_string().into());
-						};
-						program.mutate(&mut react::react(
-							Lrc::clone(&source_map),
-							Some(&comments),
-							react_options,
-							top_level_mark,
-							unresolved_mark,
-						));
-					}
+		let mut did_transform = false;
 
-					// rename old imports to new imports
-					program.visit_mut_with(&mut RenameTransform);
+		if transpile_ts && is_type_script {
+			did_transform = true;
+			timer.time("typescript", || {
+				program.mutate(&mut typescript::strip(Default::default(), top_level_mark))
+			});
+			dumper.dump("typescript", &program, &comments);
+		}
 
-					// Resolve with mark
-					program.visit_mut_with(&mut resolver(
-						unresolved_mark,
-						top_level_mark,
-						is_type_script && !transpile_ts,
-					));
-					// Collect import/export metadata
-					let mut collect = global_collect(&program);
+		let mut jsx_attr_spans = Default::default();
+		if transpile_jsx && is_jsx {
+			did_transform = true;
+			jsx_attr_spans = collect_jsx_attr_spans(&program);
+			let mut react_options = react::Options::default();
+			if is_jsx {
+				react_options.next = Some(true);
+				react_options.throw_if_namespace = Some(false);
+				react_options.runtime = Some(react::Runtime::Automatic);
+				react_options.import_source = Some("@qwik.dev/core".to_string().into());
+			};
+			timer.time("react_jsx", || {
+				program.mutate(&mut react::react(
+					Lrc::clone(&source_map),
+					Some(&comments),
+					react_options,
+					top_level_mark,
+					unresolved_mark,
+				))
+			});
+			dumper.dump("react_jsx", &program, &comments);
+		}
 
-					let mut qt: Option<QwikTransform<'_>> = None;
-					let mut segments: Vec<Segment> = Vec::new();
+		// rename old imports to new imports
+		timer.time("rename_imports", || {
+			program.visit_mut_with(&mut RenameTransform)
+		});
+		dumper.dump("rename_imports", &program, &comments);
+
+		// Resolve with mark
+		timer.time("resolver", || {
+			program.visit_mut_with(&mut resolver(
+				unresolved_mark,
+				top_level_mark,
+				is_type_script && !transpile_ts,
+			))
+		});
+		dumper.dump("resolver", &program, &comments);
+		run_custom_passes(
+			config.custom_passes,
+			PassPoint::BeforeQwikTransform,
+			&mut program,
+			&pass_ctx,
+			&mut timer,
+			&mut dumper,
+			&comments,
+		);
+		// Collect import/export metadata
+		let mut collect = timer.time("global_collect", || global_collect(&program));
+		let verify_source = config
+			.verify
+			.then(|| VerifySource::new(&program, unresolved_mark, &collect));
+
+		let mut qt: Option<QwikTransform<'_>> = None;
+		let mut segments: Vec<Segment> = Vec::new();
+		let explain_report: Option<ExplainReport>;
+
+		{
+			let is_dev = matches!(config.mode, EmitMode::Dev | EmitMode::Hmr);
+
+			// Reconstruct destructured props for signal forwarding.
+			// Runs for all modes including Lib, so library .qwik.mjs output
+			// already has the transformation applied. For pre-compiled library
+			// code (inlinedQrl calls), the transform skips their function bodies.
+			timer.time("props_destructuring", || {
+				transform_props_destructuring(&mut program, &mut collect, &config.core_module)
+			});
+			dumper.dump("props_destructuring", &program, &comments);
+
+			// Don't further process library code beyond QRL wrapping
+			if config.mode != EmitMode::Lib {
+				// replace const values
+				if config.mode != EmitMode::Test {
+					let mut const_replacer =
+						ConstReplacerVisitor::new(config.is_server, is_dev, &collect);
+					timer.time("const_replace", || {
+						program.visit_mut_with(&mut const_replacer)
+					});
+					dumper.dump("const_replace", &program, &comments);
+				}
+			}
 
-					{
-						let is_dev = matches!(config.mode, EmitMode::Dev | EmitMode::Hmr);
-
-						// Reconstruct destructured props for signal forwarding.
-						// Runs for all modes including Lib, so library .qwik.mjs output
-						// already has the transformation applied. For pre-compiled library
-						// code (inlinedQrl calls), the transform skips their function bodies.
-						transform_props_destructuring(
-							&mut program,
-							&mut collect,
-							&config.core_module,
-						);
-
-						// Don't further process library code beyond QRL wrapping
-						if config.mode != EmitMode::Lib {
-							// replace const values
-							if config.mode != EmitMode::Test {
-								let mut const_replacer =
-									ConstReplacerVisitor::new(config.is_server, is_dev, &collect);
-								program.visit_mut_with(&mut const_replacer);
-							}
-						}
+			// split into segments (also handles lib mode mechanical QRL wrapping)
+			let mut qwik_transform = QwikTransform::new(QwikTransformOptions {
+				path_data: &path_data,
+				dev_path: config.dev_path,
+				entry_policy: config.entry_policy,
+				explicit_extensions: config.explicit_extensions,
+				extension: extension.clone(),
+				comments: Some(&comments),
+				global_collect: collect,
+				scope: config.scope,
+				mode: config.mode,
+				core_module: config.core_module,
+				entry_strategy: config.entry_strategy,
+				reg_ctx_name: config.reg_ctx_name,
+				strip_ctx_name: config.strip_ctx_name,
+				strip_event_handlers: config.strip_event_handlers,
+				is_server: config.is_server,
+				explain: config.explain,
+				cm: Lrc::clone(&source_map),
+				jsx_attr_spans,
+			});
 
-						// split into segments (also handles lib mode mechanical QRL wrapping)
-						let mut qwik_transform = QwikTransform::new(QwikTransformOptions {
-							path_data: &path_data,
-							dev_path: config.dev_path,
-							entry_policy: config.entry_policy,
-							explicit_extensions: config.explicit_extensions,
-							extension: extension.clone(),
-							comments: Some(&comments),
-							global_collect: collect,
-							scope: config.scope,
-							mode: config.mode,
-							core_module: config.core_module,
-							entry_strategy: config.entry_strategy,
-							reg_ctx_name: config.reg_ctx_name,
-							strip_ctx_name: config.strip_ctx_name,
-							strip_event_handlers: config.strip_event_handlers,
-							is_server: config.is_server,
-							cm: Lrc::clone(&source_map),
+			// Library code is pre-built, it was linted when it was built
+			if config.mode != EmitMode::Lib {
+				timer.time("lints", || qwik_transform.lint(&program));
+			}
+			program = timer.time("qwik_transform", || program.fold_with(&mut qwik_transform));
+			// Library code only gets its QRLs wrapped, most rules are never checked
+			if config.mode != EmitMode::Lib {
+				qwik_transform.report_unused_suppressions();
+			}
+			dumper.dump("qwik_transform", &program, &comments);
+			let imports_before_dce = timer
+				.is_enabled()
+				.then(|| count_import_specifiers(&program));
+			timer.update(|stats| stats.segments_created = qwik_transform.segments.len());
+			let mut explainer = std::mem::take(&mut qwik_transform.explain);
+
+			if config.analyze_only {
+				if config.mode != EmitMode::Lib {
+					segments = qwik_transform.segments.clone();
+				}
+				qt = Some(qwik_transform);
+			} else if config.mode != EmitMode::Lib {
+				// Skip post-processing for library mode
+				let mut treeshaker = Treeshaker::new();
+				if config.minify != MinifyMode::None {
+					// remove all side effects from client, step 1
+					if !config.is_server {
+						timer.time("treeshaker", || {
+							program.visit_mut_with(&mut treeshaker.marker)
 						});
+						dumper.dump("treeshaker", &program, &comments);
+					}
 
-						// print before transform, for debugging
-						// println!(
-						// 	"{}",
-						// 	emit_source_code(
-						// 		Lrc::clone(&source_map.clone()),
-						// 		None,
-						// 		&main_module.clone(),
-						// 		config.root_dir,
-						// 		false,
-						// 	)
-						// 	.unwrap()
-						// 	.0
-						// );
-						program = program.fold_with(&mut qwik_transform);
-
-						// Skip post-processing for library mode
-						if config.mode != EmitMode::Lib {
-							let mut treeshaker = Treeshaker::new();
-							if config.minify != MinifyMode::None {
-								// remove all side effects from client, step 1
-								if !config.is_server {
-									program.visit_mut_with(&mut treeshaker.marker);
-								}
-
-								// simplify & strip unused code
-								program.mutate(&mut simplify::simplifier(
-									unresolved_mark,
-									simplify::Config {
-										dce: simplify::dce::Config {
-											preserve_imports_with_side_effects: false,
-											..Default::default()
-										},
+					// simplify & strip unused code
+					timer.time("simplify", || {
+						program.mutate(&mut simplify::simplifier(
+							unresolved_mark,
+							simplify::Config {
+								dce: simplify::dce::Config {
+									preserve_imports_with_side_effects: false,
+									..Default::default()
+								},
+								..Default::default()
+							},
+						))
+					});
+					dumper.dump("simplify", &program, &comments);
+				}
+				if matches!(
+					config.entry_strategy,
+					EntryStrategy::Inline | EntryStrategy::Hoist
+				) {
+					timer.time("side_effects", || {
+						program.visit_mut_with(&mut SideEffectVisitor::new(
+							&qwik_transform.options.global_collect,
+							&path_data,
+							config.src_dir,
+						))
+					});
+					dumper.dump("side_effects", &program, &comments);
+				} else if config.minify != MinifyMode::None && !config.is_server {
+					// remove all side effects from client, step 2
+					timer.time("treeshaker", || {
+						program.visit_mut_with(&mut treeshaker.cleaner)
+					});
+					dumper.dump("treeshaker", &program, &comments);
+					if treeshaker.cleaner.did_drop {
+						timer.time("simplify", || {
+							program.mutate(&mut simplify::simplifier(
+								unresolved_mark,
+								simplify::Config {
+									dce: simplify::dce::Config {
+										preserve_imports_with_side_effects: false,
 										..Default::default()
 									},
-								));
+									..Default::default()
+								},
+							))
+						});
+						dumper.dump("simplify", &program, &comments);
+					}
+				}
+				segments = qwik_transform.segments.clone();
+				qt = Some(qwik_transform);
+
+				// Apply variable migration: move segment-exclusive root variables to their segments
+				if !segments.is_empty() {
+					let q = qt.as_mut().unwrap();
+					if let ast::Program::Module(ref mut module) = &mut program {
+						let migrated_ids = timer.time("variable_migration", || {
+							let migrated_ids = apply_variable_migration(
+								&mut segments,
+								module,
+								&mut q.options.global_collect,
+								&mut explainer,
+							);
+
+							// Remove migrated variables from root module exports
+							if !migrated_ids.is_empty() {
+								for id in &migrated_ids {
+									q.options.global_collect.remove_root_and_exports_for_id(id);
+								}
+								remove_migrated_exports(module, &migrated_ids);
+								remove_unused_qrl_declarations(module);
 							}
-							if matches!(
-								config.entry_strategy,
-								EntryStrategy::Inline | EntryStrategy::Hoist
-							) {
-								program.visit_mut_with(&mut SideEffectVisitor::new(
-									&qwik_transform.options.global_collect,
-									&path_data,
-									config.src_dir,
-								));
-							} else if config.minify != MinifyMode::None && !config.is_server {
-								// remove all side effects from client, step 2
-								program.visit_mut_with(&mut treeshaker.cleaner);
-								if treeshaker.cleaner.did_drop {
+							migrated_ids
+						});
+						timer.update(|stats| stats.vars_migrated = migrated_ids.len());
+						dumper.dump("variable_migration", &program, &comments);
+
+						if !migrated_ids.is_empty() {
+							// Re-run DCE to remove imports that are no longer used
+							// after migrating variables to segments
+							if config.minify != MinifyMode::None {
+								timer.time("simplify", || {
 									program.mutate(&mut simplify::simplifier(
 										unresolved_mark,
 										simplify::Config {
@@ -393,283 +713,385 @@ pub fn transform_code(config: TransformCodeOptions) -> Result<TransformOutput, a
 											},
 											..Default::default()
 										},
-									));
-								}
-							}
-							segments = qwik_transform.segments.clone();
-							qt = Some(qwik_transform);
-
-							// Apply variable migration: move segment-exclusive root variables to their segments
-							if !segments.is_empty() {
-								let q = qt.as_mut().unwrap();
-								if let ast::Program::Module(ref mut module) = &mut program {
-									let migrated_ids = apply_variable_migration(
-										&mut segments,
-										module,
-										&mut q.options.global_collect,
-									);
-
-									// Remove migrated variables from root module exports
-									if !migrated_ids.is_empty() {
-										for id in &migrated_ids {
-											q.options
-												.global_collect
-												.remove_root_and_exports_for_id(id);
-										}
-										remove_migrated_exports(module, &migrated_ids);
-										remove_unused_qrl_declarations(module);
-
-										// Re-run DCE to remove imports that are no longer used
-										// after migrating variables to segments
-										if config.minify != MinifyMode::None {
-											program.mutate(&mut simplify::simplifier(
-												unresolved_mark,
-												simplify::Config {
-													dce: simplify::dce::Config {
-														preserve_imports_with_side_effects: false,
-														..Default::default()
-													},
-													..Default::default()
-												},
-											));
-										}
-									}
-								}
+									))
+								});
+								dumper.dump("simplify", &program, &comments);
 							}
 						}
 					}
-					program.visit_mut_with(&mut hygiene_with_config(Default::default()));
-					program.visit_mut_with(&mut fixer(None));
-
-					let mut modules: Vec<TransformModule> = Vec::with_capacity(segments.len() + 10);
-
-					let comments_maps = comments.clone().take_all();
-					// Now process each segment
-					// Note: noop segments (entry: None, e.g. routeLoader$) still generate
-					// code here but their SegmentAnalysis is what matters for manifest generation
-					if !segments.is_empty() {
-						let q = qt.as_ref().unwrap();
-						for h in segments.into_iter() {
-							let is_entry = h.entry.is_none();
-							let path_str = h.data.path.to_string();
-							let path = if path_str.is_empty() {
-								path_str
-							} else {
-								[&path_str, "/"].concat()
-							};
-							let segment_path = [
-								path,
-								[&h.canonical_filename, ".", &h.data.extension].concat(),
-							]
-							.concat();
-
-							// Create explicit imports for dev-mode QRL helpers
-							let mut explicit_imports = IndexMap::new();
-							if matches!(config.mode, EmitMode::Dev | EmitMode::Hmr) {
-								use swc_common::SyntaxContext;
-								let dev_import = Import {
-									source: q.options.core_module.clone(),
-									specifier: (*_QRL_DEV).clone(),
-									kind: ImportKind::Named,
-									synthetic: true,
-									asserts: None,
-								};
-								explicit_imports.insert(
-									((*_QRL_DEV).clone(), SyntaxContext::empty()),
-									dev_import.clone(),
-								);
-
-								let inlined_dev_import = Import {
-									source: q.options.core_module.clone(),
-									specifier: (*_INLINED_QRL_DEV).clone(),
-									kind: ImportKind::Named,
-									synthetic: true,
-									asserts: None,
-								};
-								explicit_imports.insert(
-									((*_INLINED_QRL_DEV).clone(), SyntaxContext::empty()),
-									inlined_dev_import,
-								);
-
-								let noop_dev_import = Import {
-									source: q.options.core_module.clone(),
-									specifier: (*_NOOP_QRL_DEV).clone(),
-									kind: ImportKind::Named,
-									synthetic: true,
-									asserts: None,
-								};
-								explicit_imports.insert(
-									((*_NOOP_QRL_DEV).clone(), SyntaxContext::empty()),
-									noop_dev_import,
-								);
-							}
-
-							let (mut segment_module, comments) = new_module(NewModuleCtx {
-								expr: h.expr,
-								path: &path_data,
-								name: &h.name,
-								local_idents: &h.data.local_idents,
-								scoped_idents: &h.data.scoped_idents,
-								need_transform: h.data.need_transform,
-								explicit_extensions: q.options.explicit_extensions,
-								global: &q.options.global_collect,
-								core_module: &q.options.core_module,
-								leading_comments: comments_maps.0.clone(),
-								trailing_comments: comments_maps.1.clone(),
-								extra_top_items: &q.extra_top_items,
-								migrated_root_vars: &h.data.migrated_root_vars,
-								explicit_imports: &explicit_imports,
-							})?;
-							// we don't need to remove side effects because the optimizer only moves what's really used
-							if config.minify != MinifyMode::None {
-								let mut program = ast::Program::Module(segment_module);
-								program.mutate(&mut simplify::simplifier(
-									unresolved_mark,
-									simplify::Config {
-										dce: simplify::dce::Config {
-											preserve_imports_with_side_effects: false,
-											..Default::default()
-										},
-										..Default::default()
-									},
-								));
-								segment_module = program.expect_module();
-							}
-							segment_module
-								.visit_mut_with(&mut hygiene_with_config(Default::default()));
-							segment_module.visit_mut_with(&mut fixer(None));
-							add_section_separators(&mut segment_module, &comments);
-
-							let (code, map) = emit_source_code(
-								Lrc::clone(&source_map),
-								Some(comments),
-								&segment_module,
-								config.root_dir,
-								config.source_maps,
+				}
+			}
+			explain_report = explainer.finish(config.relative_path, &segments);
+			if let Some(imports_before_dce) = imports_before_dce {
+				let imports_after_dce = count_import_specifiers(&program);
+				timer.update(|stats| {
+					stats.imports_dropped = imports_before_dce.saturating_sub(imports_after_dce)
+				});
+			}
+		}
+		if config.analyze_only {
+			let analysis = qt.as_ref().map(|q| {
+				let global_collect = &q.options.global_collect;
+				ModuleAnalysis {
+					path: config.relative_path.to_string(),
+					segments: segments
+						.into_iter()
+						.map(|h| {
+							segment_analysis(
+								&h,
+								global_collect,
+								&path_data,
+								&source_map,
+								config.explicit_extensions,
 							)
-							.unwrap();
-
-							modules.push(TransformModule {
-								code,
-								map,
-								is_entry,
-								path: segment_path,
-								imports: vec![],
-								order: h.hash,
-								segment: Some(SegmentAnalysis {
-									origin: h.data.origin,
-									name: h.name,
-									entry: h.entry,
-									extension: h.data.extension,
-									canonical_filename: h.canonical_filename,
-									path: h.data.path,
-									parent: h.data.parent_segment,
-									ctx_kind: h.data.ctx_kind,
-									ctx_name: h.data.ctx_name,
-									captures: !h.data.scoped_idents.is_empty(),
-									display_name: h.data.display_name,
-									hash: h.data.hash,
-									loc: (h.span.lo.0, h.span.hi.0),
-									param_names: h.param_names,
-									capture_names: if h.data.scoped_idents.is_empty() {
-										None
-									} else {
-										Some(
-											h.data
-												.scoped_idents
-												.iter()
-												.map(|id| id.0.clone())
-												.collect(),
-										)
-									},
-								}),
-							});
-						}
-					}
-
-					let imports = qt
-						.as_ref()
-						.map(|q| {
-							let mut imports: Vec<_> = q
-								.options
-								.global_collect
-								.imports
-								.values()
-								.map(|import| import.source.clone())
-								.collect();
-							imports
-								.extend(q.options.global_collect.dynamic_imports.iter().cloned());
-							imports.sort();
-							imports.dedup();
-							imports
 						})
-						.unwrap_or_default();
-
-					let (code, map) = match program {
-						ast::Program::Module(ref mut modu) => {
-							add_section_separators(modu, &comments);
-							emit_source_code(
-								Lrc::clone(&source_map),
-								Some(comments),
-								modu,
-								config.root_dir,
-								config.source_maps,
-							)?
-						}
-						_ => (String::new(), None),
+						.collect(),
+					imports: root_imports(global_collect),
+					exports: export_names(global_collect),
+				}
+			});
+			return Ok(TransformOutput {
+				modules: vec![],
+				diagnostics: apply_rule_severities(
+					handle_error(&error_buffer, origin, &source_map),
+					config.rules,
+				),
+				is_type_script,
+				is_jsx,
+				stats: timer.finish().into_iter().collect(),
+				explain: explain_report.into_iter().collect(),
+				debug_dumps: dumper
+					.finish()
+					.map_err(|err| OptimizerError::transform(config.relative_path, err))?,
+				size_report: None,
+				analysis: analysis.into_iter().collect(),
+			});
+		}
+		run_custom_passes(
+			config.custom_passes,
+			PassPoint::AfterSegmentation,
+			&mut program,
+			&pass_ctx,
+			&mut timer,
+			&mut dumper,
+			&comments,
+		);
+		timer.time("hygiene", || {
+			program.visit_mut_with(&mut hygiene_with_config(Default::default()));
+			program.visit_mut_with(&mut fixer(None));
+		});
+		dumper.dump("hygiene", &program, &comments);
+		let root_program = keep_program.then(|| {
+			let (leading, trailing) = comments.borrow_all();
+			RootProgram {
+				program: program.clone(),
+				leading_comments: (*leading).clone(),
+				trailing_comments: (*trailing).clone(),
+			}
+		});
+
+		let mut modules: Vec<TransformModule> = Vec::with_capacity(segments.len() + 10);
+
+		let comments_maps = comments.clone().take_all();
+		// Now process each segment
+		// Note: noop segments (entry: None, e.g. routeLoader$) still generate
+		// code here but their SegmentAnalysis is what matters for manifest generation
+		if !segments.is_empty() {
+			let q = qt.as_ref().unwrap();
+			for h in segments.into_iter() {
+				let is_entry = h.entry.is_none();
+				let mut analysis = segment_analysis(
+					&h,
+					&q.options.global_collect,
+					&path_data,
+					&source_map,
+					q.options.explicit_extensions,
+				);
+				let path_str = h.data.path.to_string();
+				let path = if path_str.is_empty() {
+					path_str
+				} else {
+					[&path_str, "/"].concat()
+				};
+				let segment_path = [
+					path,
+					[&h.canonical_filename, ".", &h.data.extension].concat(),
+				]
+				.concat();
+
+				// Create explicit imports for dev-mode QRL helpers
+				let mut explicit_imports = IndexMap::new();
+				if matches!(config.mode, EmitMode::Dev | EmitMode::Hmr) {
+					use swc_common::SyntaxContext;
+					let dev_import = Import {
+						source: q.options.core_module.clone(),
+						specifier: (*_QRL_DEV).clone(),
+						kind: ImportKind::Named,
+						synthetic: true,
+						asserts: None,
 					};
+					explicit_imports.insert(
+						((*_QRL_DEV).clone(), SyntaxContext::empty()),
+						dev_import.clone(),
+					);
 
-					let a = if did_transform && !config.preserve_filenames {
-						[&path_data.file_stem, ".", &extension].concat()
-					} else {
-						path_data.file_name
+					let inlined_dev_import = Import {
+						source: q.options.core_module.clone(),
+						specifier: (*_INLINED_QRL_DEV).clone(),
+						kind: ImportKind::Named,
+						synthetic: true,
+						asserts: None,
+					};
+					explicit_imports.insert(
+						((*_INLINED_QRL_DEV).clone(), SyntaxContext::empty()),
+						inlined_dev_import,
+					);
+
+					let noop_dev_import = Import {
+						source: q.options.core_module.clone(),
+						specifier: (*_NOOP_QRL_DEV).clone(),
+						kind: ImportKind::Named,
+						synthetic: true,
+						asserts: None,
+					};
+					explicit_imports.insert(
+						((*_NOOP_QRL_DEV).clone(), SyntaxContext::empty()),
+						noop_dev_import,
+					);
+				}
+
+				let (mut segment_module, comments) = timer
+					.time("new_module", || {
+						new_module(NewModuleCtx {
+							expr: h.expr,
+							path: &path_data,
+							name: &h.name,
+							local_idents: &h.data.local_idents,
+							scoped_idents: &h.data.scoped_idents,
+							need_transform: h.data.need_transform,
+							explicit_extensions: q.options.explicit_extensions,
+							global: &q.options.global_collect,
+							core_module: &q.options.core_module,
+							leading_comments: comments_maps.0.clone(),
+							trailing_comments: comments_maps.1.clone(),
+							extra_top_items: &q.extra_top_items,
+							migrated_root_vars: &h.data.migrated_root_vars,
+							explicit_imports: &explicit_imports,
+						})
+					})
+					.map_err(|err| OptimizerError::codegen(config.relative_path, err))?;
+				dumper.dump_segment(&h.name, "new_module", &segment_module, &comments);
+				if config.custom_passes.has(PassPoint::Segment) {
+					let segment_ctx = PassContext {
+						segment: Some(&h.name),
+						..pass_ctx
 					};
-					let path = path_data.rel_dir.join(a).to_slash_lossy().to_string();
-
-					let mut hasher = DefaultHasher::new();
-					hasher.write(path.as_bytes());
-
-					modules.push(TransformModule {
-						is_entry: false,
-						path,
-						code,
-						imports,
-						map,
-						order: hasher.finish(),
-						segment: None,
+					let mut program = ast::Program::Module(segment_module);
+					timer.time("custom_passes", || {
+						config
+							.custom_passes
+							.run(PassPoint::Segment, &mut program, &segment_ctx)
+					});
+					segment_module = program.expect_module();
+					dumper.dump_segment(&h.name, "custom_passes", &segment_module, &comments);
+				}
+				// we don't need to remove side effects because the optimizer only moves what's really used
+				if config.minify != MinifyMode::None {
+					let mut program = ast::Program::Module(segment_module);
+					timer.time("simplify", || {
+						program.mutate(&mut simplify::simplifier(
+							unresolved_mark,
+							simplify::Config {
+								dce: simplify::dce::Config {
+									preserve_imports_with_side_effects: false,
+									..Default::default()
+								},
+								..Default::default()
+							},
+						))
 					});
+					segment_module = program.expect_module();
+					dumper.dump_segment(&h.name, "simplify", &segment_module, &comments);
+				}
+				timer.time("hygiene", || {
+					segment_module.visit_mut_with(&mut hygiene_with_config(Default::default()));
+					segment_module.visit_mut_with(&mut fixer(None));
+				});
+				dumper.dump_segment(&h.name, "hygiene", &segment_module, &comments);
+				add_section_separators(&mut segment_module, &comments);
 
-					let diagnostics = handle_error(&error_buffer, origin, &source_map);
-					Ok(TransformOutput {
-						modules,
-						diagnostics,
-						is_type_script,
-						is_jsx,
+				let (code, map) = timer
+					.time("codegen", || {
+						emit_source_code(
+							Lrc::clone(&source_map),
+							Some(comments),
+							&segment_module,
+							config.root_dir,
+							config.source_maps,
+						)
 					})
-				})
-			})
+					.map_err(|err| OptimizerError::codegen(config.relative_path, err))?;
+
+				analysis.size = code.len();
+				modules.push(TransformModule {
+					code,
+					map,
+					is_entry,
+					path: segment_path,
+					imports: vec![],
+					order: h.hash,
+					program: None,
+					segment: Some(analysis),
+				});
+			}
 		}
-		Err(err) => {
-			let error_buffer = ErrorBuffer::default();
-			let handler = Handler::with_emitter(true, false, Box::new(error_buffer.clone()));
-			err.into_diagnostic(&handler).emit();
-			let diagnostics = handle_error(&error_buffer, origin, &source_map);
-			Ok(TransformOutput {
-				modules: vec![],
-				diagnostics,
-				is_type_script: false,
-				is_jsx: false,
-			})
+
+		let imports = qt
+			.as_ref()
+			.map(|q| root_imports(&q.options.global_collect))
+			.unwrap_or_default();
+
+		let (code, map) = match program {
+			ast::Program::Module(ref mut modu) => {
+				add_section_separators(modu, &comments);
+				timer
+					.time("codegen", || {
+						emit_source_code(
+							Lrc::clone(&source_map),
+							Some(comments),
+							modu,
+							config.root_dir,
+							config.source_maps,
+						)
+					})
+					.map_err(|err| OptimizerError::codegen(config.relative_path, err))?
+			}
+			_ => (String::new(), None),
+		};
+
+		let a = if did_transform && !config.preserve_filenames {
+			[&path_data.file_stem, ".", &extension].concat()
+		} else {
+			path_data.file_name
+		};
+		let path = path_data.rel_dir.join(a).to_slash_lossy().to_string();
+
+		let mut hasher = DefaultHasher::new();
+		hasher.write(path.as_bytes());
+
+		modules.push(TransformModule {
+			is_entry: false,
+			path,
+			code,
+			imports,
+			map,
+			order: hasher.finish(),
+			segment: None,
+			program: root_program,
+		});
+
+		let mut diagnostics = handle_error(&error_buffer, origin, &source_map);
+		// A transform that reported errors is expected to emit broken code
+		let has_errors = diagnostics
+			.iter()
+			.any(|d| d.category == DiagnosticCategory::Error);
+		if let (Some(source), false) = (verify_source, has_errors) {
+			diagnostics.extend(timer.time("verify", || {
+				verify_modules(config.relative_path, &modules, &source)
+			}));
 		}
+		Ok(TransformOutput {
+			modules,
+			diagnostics: apply_rule_severities(diagnostics, config.rules),
+			is_type_script,
+			is_jsx,
+			stats: timer.finish().into_iter().collect(),
+			explain: explain_report.into_iter().collect(),
+			debug_dumps: dumper
+				.finish()
+				.map_err(|err| OptimizerError::transform(config.relative_path, err))?,
+			size_report: None,
+			analysis: vec![],
+		})
+	})
+}
+
+/// The metadata of a segment, `size` is left at 0 until its code is emitted.
+fn segment_analysis(
+	h: &Segment,
+	global_collect: &GlobalCollect,
+	path_data: &PathData,
+	source_map: &SourceMap,
+	explicit_extensions: bool,
+) -> SegmentAnalysis {
+	let location = if h.span.is_dummy() {
+		None
+	} else {
+		Some(SourceLocation::from(source_map, h.span))
+	};
+	let captured = h
+		.data
+		.scoped_ident_types
+		.iter()
+		.filter(|(id, _)| h.data.scoped_idents.contains(id))
+		.map(|(id, kind)| SegmentCapture {
+			name: id.0.clone(),
+			kind: *kind,
+		})
+		.collect();
+	let imports = get_segment_imports(
+		&h.data.local_idents,
+		global_collect,
+		path_data,
+		explicit_extensions,
+	);
+	SegmentAnalysis {
+		origin: h.data.origin.clone(),
+		name: h.name.clone(),
+		entry: h.entry.clone(),
+		extension: h.data.extension.clone(),
+		canonical_filename: h.canonical_filename.clone(),
+		path: h.data.path.clone(),
+		parent: h.data.parent_segment.clone(),
+		ctx_kind: h.data.ctx_kind,
+		ctx_name: h.data.ctx_name.clone(),
+		captures: !h.data.scoped_idents.is_empty(),
+		display_name: h.data.display_name.clone(),
+		hash: h.data.hash.clone(),
+		loc: (h.span.lo.0, h.span.hi.0),
+		location,
+		param_names: h.param_names.clone(),
+		capture_names: if h.data.scoped_idents.is_empty() {
+			None
+		} else {
+			Some(h.data.scoped_idents.iter().map(|id| id.0.clone()).collect())
+		},
+		captured,
+		imports,
+		size: 0,
+		parents: h.data.ancestors.clone(),
 	}
 }
 
+/// Sorted sources of the static and dynamic imports of the root module.
+fn root_imports(global_collect: &GlobalCollect) -> Vec<Atom> {
+	let mut imports: Vec<_> = global_collect
+		.imports
+		.values()
+		.map(|import| import.source.clone())
+		.collect();
+	imports.extend(global_collect.dynamic_imports.iter().cloned());
+	imports.sort();
+	imports.dedup();
+	imports
+}
+
 fn parse(
 	code: &str,
 	path_data: &PathData,
 	root_dir: Option<&Path>,
 	source_map: Lrc<SourceMap>,
-) -> PResult<(ast::Program, SingleThreadedComments, bool, bool)> {
+) -> PResult<(ast::Program, SingleThreadedComments)> {
 	let sm_path = if let Some(root_dir) = root_dir {
 		pathdiff::diff_paths(path_data.abs_path.clone(), root_dir).unwrap()
 	} else {
@@ -703,7 +1125,7 @@ fn parse(
 	let mut parser = Parser::new_from(lexer);
 	match parser.parse_program() {
 		Err(err) => Err(err),
-		Ok(result) => Ok((result, comments, is_type_script, is_jsx)),
+		Ok(result) => Ok((result, comments)),
 	}
 }
 
@@ -776,6 +1198,16 @@ pub fn emit_source_code(
 	}
 }
 
+/// Copies the comment store. Cloning `SingleThreadedComments` shares it, and the emitter takes
+/// the comments it prints out of the store.
+pub fn copy_comments(comments: &SingleThreadedComments) -> SingleThreadedComments {
+	let (leading, trailing) = comments.borrow_all();
+	SingleThreadedComments::from_leading_and_trailing(
+		Rc::new(RefCell::new((*leading).clone())),
+		Rc::new(RefCell::new((*trailing).clone())),
+	)
+}
+
 /// Add `//` separator comments between import declarations, QRL const declarations,
 /// and other module-level statements for readability. Uses `Span::dummy_with_cmt()`
 /// so the extra lines don't affect source maps.
@@ -871,6 +1303,41 @@ fn add_section_separators(module: &mut ast::Module, comments: &SingleThreadedCom
 	}
 }
 
+/// Resolves the imports a segment needs for its local idents: either an import of the
+/// origin module, or an export of the origin module itself.
+fn get_segment_imports(
+	local_idents: &[Id],
+	global: &GlobalCollect,
+	path_data: &PathData,
+	explicit_extensions: bool,
+) -> Vec<SegmentImport> {
+	let mut imports: Vec<SegmentImport> = local_idents
+		.iter()
+		.filter_map(|id| {
+			if let Some(import) = global.imports.get(id) {
+				Some(SegmentImport {
+					source: import.source.clone(),
+					specifier: import.specifier.clone(),
+				})
+			} else {
+				let export = global.resolve_export_name_for_id(id)?;
+				let filename = if explicit_extensions {
+					&path_data.file_name
+				} else {
+					&path_data.file_stem
+				};
+				Some(SegmentImport {
+					source: format!("./{}", filename).into(),
+					specifier: export.unwrap_or_else(|| id.0.clone()),
+				})
+			}
+		})
+		.collect();
+	imports.sort();
+	imports.dedup();
+	imports
+}
+
 fn handle_error(
 	error_buffer: &ErrorBuffer,
 	origin: Atom,
@@ -914,6 +1381,26 @@ fn handle_error(
 				)
 			};
 
+			// Suggestions that carry a substitution are fixes, the first substitution is applied
+			let fixes = suggestions
+				.iter()
+				.filter_map(|suggestion| {
+					let substitution = suggestion.substitutions.first()?;
+					Some(Fix {
+						message: suggestion.msg.clone(),
+						safe: suggestion.applicability == Applicability::MachineApplicable,
+						edits: substitution
+							.parts
+							.iter()
+							.map(|part| TextEdit {
+								range: EditRange::from(source_map, part.span),
+								replacement: part.snippet.clone(),
+							})
+							.collect(),
+					})
+				})
+				.collect();
+
 			let suggestions = if suggestions.is_empty() {
 				None
 			} else {
@@ -937,6 +1424,7 @@ fn handle_error(
 					DiagnosticCategory::Error
 				},
 				scope: DiagnosticScope::Optimizer,
+				fixes,
 			}
 		})
 		.collect()
@@ -952,7 +1440,7 @@ pub struct PathData {
 	pub file_name: String,
 }
 
-pub fn parse_path(src: &str, base_dir: &Path) -> Result<PathData, Error> {
+pub fn parse_path(src: &str, base_dir: &Path) -> Result<PathData, OptimizerError> {
 	let path = Path::new(src);
 	let lossy = path.to_slash_lossy();
 	let path = Path::new(lossy.as_ref());
@@ -960,14 +1448,17 @@ pub fn parse_path(src: &str, base_dir: &Path) -> Result<PathData, Error> {
 		.file_stem()
 		.and_then(OsStr::to_str)
 		.map(Into::into)
-		.with_context(|| format!("Computing file stem for {}", path.to_string_lossy()))?;
+		.ok_or_else(|| OptimizerError::path(src, "Computing file stem"))?;
 
-	let rel_dir = path.parent().unwrap().to_path_buf();
-	let extension = path.extension().and_then(OsStr::to_str).unwrap();
+	let rel_dir = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+	let extension = path
+		.extension()
+		.and_then(OsStr::to_str)
+		.ok_or_else(|| OptimizerError::path(src, "Computing file extension"))?;
 	let file_name = path
 		.file_name()
 		.and_then(OsStr::to_str)
-		.with_context(|| format!("Computing filename for {}", path.to_string_lossy()))?;
+		.ok_or_else(|| OptimizerError::path(src, "Computing filename"))?;
 
 	let abs_path = normalize_path(base_dir.join(path));
 	let abs_dir = normalize_path(abs_path.parent().unwrap());
@@ -1004,6 +1495,21 @@ pub fn normalize_path<P: AsRef<Path>>(path: P) -> PathBuf {
 	normalized
 }
 
+fn run_custom_passes(
+	passes: &CustomPasses,
+	point: PassPoint,
+	program: &mut ast::Program,
+	ctx: &PassContext,
+	timer: &mut PassTimer,
+	dumper: &mut PassDumper,
+	comments: &SingleThreadedComments,
+) {
+	if passes.has(point) {
+		timer.time("custom_passes", || passes.run(point, program, ctx));
+		dumper.dump("custom_passes", program, comments);
+	}
+}
+
 /// Analyzes root variables and migrates segment-exclusive ones into their respective segments.
 /// This reduces the parent module footprint and improves code chunking.
 /// Returns the set of Id's that were migrated, so they can be removed from root exports.
@@ -1011,6 +1517,7 @@ fn apply_variable_migration(
 	segments: &mut [Segment],
 	module: &mut ast::Module,
 	global_collect: &mut crate::collector::GlobalCollect,
+	explainer: &mut Explainer,
 ) -> std::collections::HashSet<Id> {
 	// Analyze root variable dependencies
 	let root_dependencies = analyze_root_dependencies(module, global_collect);
@@ -1027,6 +1534,17 @@ fn apply_variable_migration(
 		&root_var_usage,
 		&main_module_usage,
 	);
+	if explainer.is_enabled() {
+		let decisions = explain_migration(
+			&root_dependencies,
+			&root_var_usage,
+			&main_module_usage,
+			&migratable,
+		);
+		for (seg_idx, decision) in decisions {
+			explainer.record(segments.get(seg_idx).map(|s| &s.name), || decision);
+		}
+	}
 
 	// PHASE 1: Pre-declare all needed auto-exports BEFORE migration
 	// This ensures all dependencies are known upfront, preventing surprises during migration
@@ -1711,10 +2229,7 @@ mod migration_cleanup_tests {
 			let mut module = ast::Module {
 				span: DUMMY_SP,
 				shebang: None,
-				body: vec![
-					make_decl(ident_a.clone(), "a"),
-					make_decl(ident_b.clone(), "b"),
-				],
+				body: vec![make_decl(ident_a.clone(), "a"), make_decl(ident_b, "b")],
 			};
 
 			let mut migrated_ids: HashSet<Id> = HashSet::new();
@@ -0,0 +1,337 @@
+use crate::parse::{normalize_path, TransformOutput};
+use crate::utils::strip_extension;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+use swc_atoms::Atom;
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphFormat {
+	Json,
+	Dot,
+	Mermaid,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphNodeKind {
+	/// A transformed input file
+	Root,
+	/// An extracted segment
+	Segment,
+	/// An entry that groups segments into one chunk
+	Entry,
+	/// A module that is imported but not part of the output
+	External,
+}
+
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum GraphEdgeKind {
+	/// From a root or segment to a segment extracted out of it
+	Parent,
+	/// From a segment to the parent whose scope it captures
+	Capture,
+	/// From a segment to the entry it is grouped into
+	Entry,
+	/// From a root or segment to a module it imports
+	Import,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphNode {
+	pub id: Atom,
+	pub kind: GraphNodeKind,
+	pub label: Atom,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphEdge {
+	pub from: Atom,
+	pub to: Atom,
+	pub kind: GraphEdgeKind,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub labels: Vec<Atom>,
+}
+
+impl GraphEdge {
+	fn label_text(&self) -> String {
+		self.labels
+			.iter()
+			.map(|l| l.as_ref())
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
+
+/// The segment tree of a `TransformOutput`, with the edges that explain how a file was split.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SegmentGraph {
+	pub nodes: Vec<GraphNode>,
+	pub edges: Vec<GraphEdge>,
+}
+
+impl SegmentGraph {
+	pub fn from_output(output: &TransformOutput) -> Self {
+		let mut nodes: BTreeMap<Atom, GraphNode> = BTreeMap::new();
+		let mut edges: BTreeSet<GraphEdge> = BTreeSet::new();
+
+		// Root modules are keyed by their path without extension, so segment origins
+		// (which keep the input extension) and relative imports can be matched against them.
+		let mut roots: BTreeMap<String, Atom> = BTreeMap::new();
+		for module in output.modules.iter().filter(|m| m.segment.is_none()) {
+			let id = Atom::from(module.path.as_str());
+			roots.insert(strip_extension(&module.path), id.clone());
+			nodes.insert(
+				id.clone(),
+				GraphNode {
+					label: id.clone(),
+					id,
+					kind: GraphNodeKind::Root,
+				},
+			);
+		}
+
+		for module in &output.modules {
+			let Some(segment) = &module.segment else {
+				continue;
+			};
+			nodes.insert(
+				segment.name.clone(),
+				GraphNode {
+					id: segment.name.clone(),
+					kind: GraphNodeKind::Segment,
+					label: segment.display_name.clone(),
+				},
+			);
+
+			let parent = segment.parent.clone().or_else(|| {
+				roots
+					.get(&strip_extension(&segment.origin))
+					.cloned()
+					.or_else(|| Some(segment.origin.clone()))
+			});
+			if let Some(parent) = parent {
+				nodes.entry(parent.clone()).or_insert_with(|| GraphNode {
+					id: parent.clone(),
+					kind: GraphNodeKind::Root,
+					label: parent.clone(),
+				});
+				edges.insert(GraphEdge {
+					from: parent.clone(),
+					to: segment.name.clone(),
+					kind: GraphEdgeKind::Parent,
+					labels: vec![],
+				});
+				if !segment.captured.is_empty() {
+					edges.insert(GraphEdge {
+						from: segment.name.clone(),
+						to: parent,
+						kind: GraphEdgeKind::Capture,
+						labels: segment.captured.iter().map(|c| c.name.clone()).collect(),
+					});
+				}
+			}
+
+			if let Some(entry) = &segment.entry {
+				nodes.entry(entry.clone()).or_insert_with(|| GraphNode {
+					id: entry.clone(),
+					kind: GraphNodeKind::Entry,
+					label: entry.clone(),
+				});
+				edges.insert(GraphEdge {
+					from: segment.name.clone(),
+					to: entry.clone(),
+					kind: GraphEdgeKind::Entry,
+					labels: vec![],
+				});
+			}
+
+			let mut imports: BTreeMap<Atom, Vec<Atom>> = BTreeMap::new();
+			for import in &segment.imports {
+				imports
+					.entry(import.source.clone())
+					.or_default()
+					.push(import.specifier.clone());
+			}
+			for (source, specifiers) in imports {
+				let target = resolve_import(&roots, &segment.path, &source);
+				add_import_edge(&mut nodes, &mut edges, &segment.name, target, specifiers);
+			}
+		}
+
+		for module in output.modules.iter().filter(|m| m.segment.is_none()) {
+			let from = Atom::from(module.path.as_str());
+			let dir = Path::new(&module.path)
+				.parent()
+				.map(|p| p.to_string_lossy().to_string())
+				.unwrap_or_default();
+			for source in &module.imports {
+				let target = resolve_import(&roots, &dir, source);
+				add_import_edge(&mut nodes, &mut edges, &from, target, vec![]);
+			}
+		}
+
+		Self {
+			nodes: nodes.into_values().collect(),
+			edges: edges.into_iter().collect(),
+		}
+	}
+
+	pub fn render(&self, format: GraphFormat) -> String {
+		match format {
+			GraphFormat::Json => self.to_json(),
+			GraphFormat::Dot => self.to_dot(),
+			GraphFormat::Mermaid => self.to_mermaid(),
+		}
+	}
+
+	pub fn to_json(&self) -> String {
+		serde_json::to_string_pretty(self).unwrap_or_default()
+	}
+
+	pub fn to_dot(&self) -> String {
+		let mut out = String::from("digraph segments {\n\trankdir=LR;\n");
+		for node in &self.nodes {
+			let shape = match node.kind {
+				GraphNodeKind::Root => "box",
+				GraphNodeKind::Segment => "ellipse",
+				GraphNodeKind::Entry => "folder",
+				GraphNodeKind::External => "note",
+			};
+			let _ = writeln!(
+				out,
+				"\t\"{}\" [label=\"{}\", shape={}];",
+				escape_dot(&node.id),
+				escape_dot(&node.label),
+				shape
+			);
+		}
+		for edge in &self.edges {
+			let style = match edge.kind {
+				GraphEdgeKind::Parent => "solid",
+				GraphEdgeKind::Capture => "dashed",
+				GraphEdgeKind::Entry => "dotted",
+				GraphEdgeKind::Import => "bold",
+			};
+			let _ = write!(
+				out,
+				"\t\"{}\" -> \"{}\" [style={}",
+				escape_dot(&edge.from),
+				escape_dot(&edge.to),
+				style
+			);
+			if !edge.labels.is_empty() {
+				let _ = write!(out, ", label=\"{}\"", escape_dot(&edge.label_text()));
+			}
+			out.push_str("];\n");
+		}
+		out.push_str("}\n");
+		out
+	}
+
+	pub fn to_mermaid(&self) -> String {
+		// Mermaid ids must be plain words, so nodes are numbered and the real id goes in the label
+		let ids: BTreeMap<&Atom, String> = self
+			.nodes
+			.iter()
+			.enumerate()
+			.map(|(i, node)| (&node.id, format!("n{}", i)))
+			.collect();
+
+		let mut out = String::from("flowchart LR\n");
+		for node in &self.nodes {
+			let label = escape_mermaid(&node.label);
+			let id = &ids[&node.id];
+			let _ = match node.kind {
+				GraphNodeKind::Root => writeln!(out, "\t{}[\"{}\"]", id, label),
+				GraphNodeKind::Segment => writeln!(out, "\t{}([\"{}\"])", id, label),
+				GraphNodeKind::Entry => writeln!(out, "\t{}[[\"{}\"]]", id, label),
+				GraphNodeKind::External => writeln!(out, "\t{}>\"{}\"]", id, label),
+			};
+		}
+		for edge in &self.edges {
+			let (Some(from), Some(to)) = (ids.get(&edge.from), ids.get(&edge.to)) else {
+				continue;
+			};
+			let arrow = match edge.kind {
+				GraphEdgeKind::Parent => "-->",
+				GraphEdgeKind::Capture => "-.->",
+				GraphEdgeKind::Entry => "-.-",
+				GraphEdgeKind::Import => "==>",
+			};
+			if edge.labels.is_empty() {
+				let _ = writeln!(out, "\t{} {} {}", from, arrow, to);
+			} else {
+				let _ = writeln!(
+					out,
+					"\t{} {}|\"{}\"| {}",
+					from,
+					arrow,
+					escape_mermaid(&edge.label_text()),
+					to
+				);
+			}
+		}
+		out
+	}
+}
+
+impl TransformOutput {
+	pub fn segment_graph(&self) -> SegmentGraph {
+		SegmentGraph::from_output(self)
+	}
+}
+
+fn add_import_edge(
+	nodes: &mut BTreeMap<Atom, GraphNode>,
+	edges: &mut BTreeSet<GraphEdge>,
+	from: &Atom,
+	target: Atom,
+	labels: Vec<Atom>,
+) {
+	if &target == from {
+		return;
+	}
+	nodes.entry(target.clone()).or_insert_with(|| GraphNode {
+		id: target.clone(),
+		kind: GraphNodeKind::External,
+		label: target.clone(),
+	});
+	edges.insert(GraphEdge {
+		from: from.clone(),
+		to: target,
+		kind: GraphEdgeKind::Import,
+		labels,
+	});
+}
+
+/// Maps a relative import to the root module it points at, if it is part of the output.
+fn resolve_import(roots: &BTreeMap<String, Atom>, dir: &str, source: &Atom) -> Atom {
+	if source.starts_with('.') {
+		let joined = normalize_path(Path::new(dir).join(source.as_str()));
+		let joined = joined.to_string_lossy().replace('\\', "/");
+		let joined = joined.trim_start_matches("./");
+		if let Some(root) = roots
+			.get(joined)
+			.or_else(|| roots.get(&strip_extension(joined)))
+		{
+			return root.clone();
+		}
+	}
+	source.clone()
+}
+
+fn escape_dot(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_mermaid(s: &str) -> String {
+	s.replace('"', "#quot;")
+}
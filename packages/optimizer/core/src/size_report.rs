@@ -0,0 +1,150 @@
+use crate::errors::OptimizerError;
+use crate::parse::{SegmentAnalysis, TransformModule, TransformOutput};
+use crate::utils::strip_extension;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::ops::AddAssign;
+use swc_atoms::Atom;
+
+#[derive(Debug, Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleSizes {
+	pub raw: usize,
+	pub gzip: usize,
+	pub brotli: usize,
+}
+
+impl ModuleSizes {
+	pub fn measure(code: &str) -> io::Result<Self> {
+		Ok(Self {
+			raw: code.len(),
+			gzip: gzip_size(code.as_bytes())?,
+			brotli: brotli_size(code.as_bytes())?,
+		})
+	}
+}
+
+impl AddAssign for ModuleSizes {
+	fn add_assign(&mut self, other: Self) {
+		self.raw += other.raw;
+		self.gzip += other.gzip;
+		self.brotli += other.brotli;
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleSizeEntry {
+	pub path: String,
+	/// The segment symbol, `None` for root modules
+	pub segment: Option<Atom>,
+	/// The entry the module is bundled into, the module path itself when it is its own entry
+	pub entry: Atom,
+	pub origin: Atom,
+	/// Display name of the outermost `component$` segment the module belongs to
+	pub component: Option<Atom>,
+	#[serde(flatten)]
+	pub sizes: ModuleSizes,
+}
+
+/// Raw, gzip and brotli sizes of every emitted module, rolled up by entry, origin and component.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeReport {
+	pub modules: Vec<ModuleSizeEntry>,
+	pub entries: BTreeMap<Atom, ModuleSizes>,
+	pub origins: BTreeMap<Atom, ModuleSizes>,
+	pub components: BTreeMap<Atom, ModuleSizes>,
+	pub total: ModuleSizes,
+}
+
+impl SizeReport {
+	pub fn from_output(output: &TransformOutput) -> Result<Self, OptimizerError> {
+		let segments: HashMap<&Atom, &SegmentAnalysis> = output
+			.modules
+			.iter()
+			.filter_map(|m| m.segment.as_ref())
+			.map(|s| (&s.name, s))
+			.collect();
+
+		// Root modules don't know their input path, match them through segment origins instead
+		let origins: HashMap<String, &Atom> = segments
+			.values()
+			.map(|s| (strip_extension(&s.origin), &s.origin))
+			.collect();
+
+		let mut report = Self::default();
+		for module in &output.modules {
+			let entry = size_entry(module, &segments, &origins)?;
+			report.total += entry.sizes;
+			*report.entries.entry(entry.entry.clone()).or_default() += entry.sizes;
+			*report.origins.entry(entry.origin.clone()).or_default() += entry.sizes;
+			if let Some(component) = &entry.component {
+				*report.components.entry(component.clone()).or_default() += entry.sizes;
+			}
+			report.modules.push(entry);
+		}
+		Ok(report)
+	}
+}
+
+impl TransformOutput {
+	pub fn get_size_report(&self) -> Result<SizeReport, OptimizerError> {
+		SizeReport::from_output(self)
+	}
+}
+
+fn size_entry(
+	module: &TransformModule,
+	segments: &HashMap<&Atom, &SegmentAnalysis>,
+	origins: &HashMap<String, &Atom>,
+) -> Result<ModuleSizeEntry, OptimizerError> {
+	let path = Atom::from(module.path.as_str());
+	let sizes = ModuleSizes::measure(&module.code)
+		.map_err(|err| OptimizerError::transform(&module.path, err))?;
+	Ok(match &module.segment {
+		Some(segment) => ModuleSizeEntry {
+			path: module.path.clone(),
+			segment: Some(segment.name.clone()),
+			entry: segment.entry.clone().unwrap_or(path),
+			origin: segment.origin.clone(),
+			component: segment
+				.parents
+				.iter()
+				.chain(std::iter::once(&segment.name))
+				.filter_map(|name| segments.get(name))
+				.find(|s| s.ctx_name.as_ref() == "component$")
+				.map(|s| s.display_name.clone()),
+			sizes,
+		},
+		None => ModuleSizeEntry {
+			path: module.path.clone(),
+			segment: None,
+			entry: path.clone(),
+			origin: origins
+				.get(&strip_extension(&module.path))
+				.map_or(path, |origin| (*origin).clone()),
+			component: None,
+			sizes,
+		},
+	})
+}
+
+fn gzip_size(bytes: &[u8]) -> io::Result<usize> {
+	let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+	encoder.write_all(bytes)?;
+	Ok(encoder.finish()?.len())
+}
+
+fn brotli_size(bytes: &[u8]) -> io::Result<usize> {
+	let params = brotli::enc::BrotliEncoderParams {
+		quality: 11,
+		lgwin: 22,
+		..Default::default()
+	};
+	brotli::BrotliCompress(&mut &*bytes, &mut io::sink(), &params)
+}
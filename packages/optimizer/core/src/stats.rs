@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use swc_ecmascript::ast;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PassTiming {
+	pub name: String,
+	/// Wall-clock time in milliseconds, summed over every run of the pass
+	pub duration_ms: f64,
+	pub runs: u32,
+}
+
+/// Per-file timings and counters, collected when `stats` is enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformStats {
+	pub path: String,
+	pub passes: Vec<PassTiming>,
+	pub total_ms: f64,
+	pub segments_created: usize,
+	pub vars_migrated: usize,
+	pub imports_dropped: usize,
+}
+
+/// Measures the passes of `transform_code`. When disabled, passes run untimed.
+pub struct PassTimer {
+	stats: Option<TransformStats>,
+}
+
+impl PassTimer {
+	pub fn new(enabled: bool, path: &str) -> Self {
+		Self {
+			stats: enabled.then(|| TransformStats {
+				path: path.to_string(),
+				..TransformStats::default()
+			}),
+		}
+	}
+
+	pub fn time<T>(&mut self, name: &str, f: impl FnOnce() -> T) -> T {
+		let Some(stats) = &mut self.stats else {
+			return f();
+		};
+		let start = now_ms();
+		let result = f();
+		let duration_ms = now_ms() - start;
+		stats.total_ms += duration_ms;
+		if let Some(pass) = stats.passes.iter_mut().find(|p| p.name == name) {
+			pass.duration_ms += duration_ms;
+			pass.runs += 1;
+		} else {
+			stats.passes.push(PassTiming {
+				name: name.to_string(),
+				duration_ms,
+				runs: 1,
+			});
+		}
+		result
+	}
+
+	pub fn update(&mut self, f: impl FnOnce(&mut TransformStats)) {
+		if let Some(stats) = &mut self.stats {
+			f(stats);
+		}
+	}
+
+	pub const fn is_enabled(&self) -> bool {
+		self.stats.is_some()
+	}
+
+	pub fn finish(self) -> Option<TransformStats> {
+		self.stats
+	}
+}
+
+pub fn count_import_specifiers(program: &ast::Program) -> usize {
+	match program {
+		ast::Program::Module(module) => module
+			.body
+			.iter()
+			.map(|item| match item {
+				ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(import)) => {
+					import.specifiers.len().max(1)
+				}
+				_ => 0,
+			})
+			.sum(),
+		ast::Program::Script(_) => 0,
+	}
+}
+
+/// Milliseconds since an arbitrary start, only differences between two calls are meaningful.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now_ms() -> f64 {
+	static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+	START
+		.get_or_init(std::time::Instant::now)
+		.elapsed()
+		.as_secs_f64()
+		* 1000.0
+}
+
+/// `Instant::now()` panics on wasm32-unknown-unknown, use `performance.now()` of the JS host
+/// instead, or `Date.now()` when it has no `performance`.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now_ms() -> f64 {
+	use wasm_bindgen::JsCast;
+
+	let performance_now = || {
+		let performance = js_sys::Reflect::get(&js_sys::global(), &"performance".into()).ok()?;
+		let now = js_sys::Reflect::get(&performance, &"now".into()).ok()?;
+		now.dyn_into::<js_sys::Function>()
+			.ok()?
+			.call0(&performance)
+			.ok()?
+			.as_f64()
+	};
+	performance_now().unwrap_or_else(js_sys::Date::now)
+}
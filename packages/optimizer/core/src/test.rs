@@ -49,16 +49,16 @@ macro_rules! test_input {
 	}};
 }
 
-fn test_input_fn(input: TestInput) -> Result<TransformOutput, anyhow::Error> {
+fn test_input_fn(input: TestInput) -> Result<TransformOutput, OptimizerError> {
 	let strip_exports: Option<Vec<Atom>> = input
 		.strip_exports
-		.map(|v| v.into_iter().map(|s| Atom::from(s)).collect());
+		.map(|v| v.into_iter().map(Atom::from).collect());
 	let reg_ctx_name: Option<Vec<Atom>> = input
 		.reg_ctx_name
-		.map(|v| v.into_iter().map(|s| Atom::from(s)).collect());
+		.map(|v| v.into_iter().map(Atom::from).collect());
 	let strip_ctx_name: Option<Vec<Atom>> = input
 		.strip_ctx_name
-		.map(|v| v.into_iter().map(|s| Atom::from(s)).collect());
+		.map(|v| v.into_iter().map(Atom::from).collect());
 
 	transform_modules(TransformModulesOptions {
 		src_dir: input.src_dir,
@@ -83,7 +83,15 @@ fn test_input_fn(input: TestInput) -> Result<TransformOutput, anyhow::Error> {
 		reg_ctx_name,
 		strip_event_handlers: input.strip_event_handlers,
 		is_server: input.is_server,
-		// filler to maintain line offsets
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: input.size_report,
+		debug_dump: None,
+		// Every snapshot must also pass the emitted module checks
+		verify: true,
+		rules: Default::default(),
+		custom_passes: Default::default(),
 	})
 }
 
@@ -3479,7 +3487,14 @@ export const Local = component$(() => {
 		minify: MinifyMode::Simplify,
 		explicit_extensions: true,
 		mode: EmitMode::Test,
-		// filler to maintain line offsets
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
 		entry_strategy: EntryStrategy::Segment,
 		transpile_ts: true,
 		transpile_jsx: true,
@@ -3561,7 +3576,14 @@ export const Greeter = component$(() => {
 		root_dir: None,
 		explicit_extensions: true,
 		mode: EmitMode::Test,
-		// filler to maintain line offsets
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
 		entry_strategy: EntryStrategy::Segment,
 		transpile_ts: true,
 		transpile_jsx: true,
@@ -3601,7 +3623,14 @@ export const Greeter = component$(() => {
 			minify: MinifyMode::Simplify,
 			explicit_extensions: true,
 			mode: option.0,
-			// filler to maintain line offsets
+			stats: false,
+			explain: false,
+			manual_chunks: None,
+			size_report: false,
+			debug_dump: None,
+			verify: false,
+			rules: Default::default(),
+			custom_passes: Default::default(),
 			entry_strategy: option.1,
 			transpile_ts: option.2,
 			transpile_jsx: option.2,
@@ -6198,7 +6227,7 @@ export default component$(() => {
 }
 
 fn get_hash(name: &str) -> String {
-	name.split('_').last().unwrap().into()
+	name.split('_').next_back().unwrap().into()
 }
 
 fn get_segment_hash_by_ctx_name(output: &TransformOutput, ctx_name: &str) -> String {
@@ -6258,6 +6287,7 @@ struct TestInput {
 	pub strip_ctx_name: Option<Vec<String>>,
 	pub strip_event_handlers: bool,
 	pub is_server: Option<bool>,
+	pub size_report: bool,
 }
 
 #[test]
@@ -7252,17 +7282,21 @@ export function qwikifyQrl(reactCmp$, opts) {
 	let search = combined_code
 		.find("q_s_inner123.w(")
 		.or_else(|| combined_code.find("\"s_inner123\""))
-		.expect(&format!(
-			"Should find s_inner123 call in output.\nGenerated code:\n{}",
-			combined_code
-		));
+		.unwrap_or_else(|| {
+			panic!(
+				"Should find s_inner123 call in output.\nGenerated code:\n{}",
+				combined_code
+			)
+		});
 
 	// Find the captures array (the [...] argument)
 	let after_hash = &combined_code[search..];
-	let bracket_start = after_hash.find('[').expect(&format!(
-		"Should find captures array for s_inner123.\nGenerated code:\n{}",
-		combined_code
-	));
+	let bracket_start = after_hash.find('[').unwrap_or_else(|| {
+		panic!(
+			"Should find captures array for s_inner123.\nGenerated code:\n{}",
+			combined_code
+		)
+	});
 	let bracket_end = after_hash[bracket_start..]
 		.find(']')
 		.expect("Should find end of captures array");
@@ -7350,10 +7384,12 @@ export { MyComponent };
 	// Verify computed captures include both count and isOpen
 	let computed_captures = combined_code
 		.find("q_MyComponent_component_label_useComputed_ABC123.w(")
-		.expect(&format!(
-			"Should find computed QRL .w() call.\nGenerated code:\n{}",
-			combined_code
-		));
+		.unwrap_or_else(|| {
+			panic!(
+				"Should find computed QRL .w() call.\nGenerated code:\n{}",
+				combined_code
+			)
+		});
 	let after = &combined_code[computed_captures..];
 	let bracket_end = after.find("])").expect("Should find end of captures array");
 	let captures_str = &after[..bracket_end + 1];
@@ -7904,6 +7940,7 @@ impl TestInput {
 			strip_ctx_name: None,
 			strip_event_handlers: false,
 			is_server: None,
+			size_report: false,
 		}
 	}
 }
@@ -7948,3 +7985,1823 @@ export const task = inlinedQrl(() => {
 		entry_module.code
 	);
 }
+
+#[test]
+fn segment_analysis_metadata() {
+	let output = test_input!(TestInput {
+		code: r#"
+import { component$, useSignal, useTask$ } from '@qwik.dev/core';
+import { format } from './utils';
+
+export const helper = () => 'help';
+
+export const App = component$(() => {
+	const count = useSignal(0);
+	let label = 'count';
+	useTask$(() => {
+		count.value++;
+		console.log(format(label), helper());
+	});
+	return <div>{count.value}</div>;
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		snapshot: false,
+		..TestInput::default()
+	})
+	.unwrap();
+
+	let segments: Vec<_> = output
+		.modules
+		.iter()
+		.filter(|module| module.segment.is_some())
+		.collect();
+	let app = segments
+		.iter()
+		.map(|module| module.segment.as_ref().unwrap())
+		.find(|segment| segment.ctx_name.as_ref() == "component$")
+		.expect("component segment should exist");
+	let task_module = segments
+		.iter()
+		.find(|module| module.segment.as_ref().unwrap().ctx_name.as_ref() == "useTask$")
+		.expect("useTask$ segment should exist");
+	let task = task_module.segment.as_ref().unwrap();
+
+	let location = task.location.as_ref().expect("location should be set");
+	assert_eq!(location.start_line, 10);
+	assert_eq!(location.end_line, 13);
+	assert_eq!(task.size, task_module.code.len());
+
+	assert_eq!(task.parents, vec![app.name.clone()]);
+	assert!(app.parents.is_empty());
+
+	assert_eq!(
+		task.captured,
+		vec![
+			SegmentCapture {
+				name: "count".into(),
+				kind: crate::transform::IdentType::Var(true),
+			},
+			SegmentCapture {
+				name: "label".into(),
+				kind: crate::transform::IdentType::Var(false),
+			},
+		]
+	);
+
+	assert_eq!(
+		task.imports,
+		vec![
+			SegmentImport {
+				source: "./test".into(),
+				specifier: "helper".into(),
+			},
+			SegmentImport {
+				source: "./utils".into(),
+				specifier: "format".into(),
+			},
+		]
+	);
+}
+
+#[test]
+fn segment_graph_formats() {
+	let output = test_input!(TestInput {
+		code: r#"
+import { component$, useSignal, useTask$ } from '@qwik.dev/core';
+import { format } from './utils';
+
+export const App = component$(() => {
+	const count = useSignal(0);
+	useTask$(() => {
+		console.log(format(count.value));
+	});
+	return <div>{count.value}</div>;
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		entry_strategy: EntryStrategy::Component,
+		snapshot: false,
+		..TestInput::default()
+	})
+	.unwrap();
+
+	let graph = output.segment_graph();
+	let app = output
+		.modules
+		.iter()
+		.filter_map(|m| m.segment.as_ref())
+		.find(|s| s.ctx_name.as_ref() == "component$")
+		.unwrap();
+	let task = output
+		.modules
+		.iter()
+		.filter_map(|m| m.segment.as_ref())
+		.find(|s| s.ctx_name.as_ref() == "useTask$")
+		.unwrap();
+
+	let has_edge = |from: &str, to: &str, kind: GraphEdgeKind| {
+		graph
+			.edges
+			.iter()
+			.any(|e| e.from.as_ref() == from && e.to.as_ref() == to && e.kind == kind)
+	};
+	assert!(has_edge("test.ts", &app.name, GraphEdgeKind::Parent));
+	assert!(has_edge(&app.name, &task.name, GraphEdgeKind::Parent));
+	assert!(has_edge(&task.name, &app.name, GraphEdgeKind::Capture));
+	assert!(has_edge(
+		&task.name,
+		task.entry.as_ref().unwrap(),
+		GraphEdgeKind::Entry
+	));
+	assert!(has_edge(&task.name, "./utils", GraphEdgeKind::Import));
+	assert!(has_edge("test.ts", "@qwik.dev/core", GraphEdgeKind::Import));
+
+	let dot = graph.render(GraphFormat::Dot);
+	assert!(dot.starts_with("digraph segments {"));
+	assert!(dot.contains(&format!(
+		"\"{}\" -> \"{}\" [style=dashed, label=\"count\"];",
+		task.name, app.name
+	)));
+
+	let mermaid = graph.render(GraphFormat::Mermaid);
+	assert!(mermaid.starts_with("flowchart LR\n"));
+	assert!(mermaid.contains("-.->|\"count\"|"));
+
+	let json: SegmentGraph = serde_json::from_str(&graph.render(GraphFormat::Json)).unwrap();
+	assert_eq!(json, graph);
+}
+
+#[test]
+fn size_report_rollups() {
+	let output = test_input!(TestInput {
+		code: r#"
+import { component$, useSignal, useTask$ } from '@qwik.dev/core';
+
+export const App = component$(() => {
+	const count = useSignal(0);
+	useTask$(() => {
+		console.log(count.value);
+	});
+	return <div>{count.value}</div>;
+});
+"#
+		.to_string(),
+		transpile_jsx: true,
+		entry_strategy: EntryStrategy::Component,
+		snapshot: false,
+		size_report: true,
+		..TestInput::default()
+	})
+	.unwrap();
+
+	let report = output.size_report.as_ref().unwrap();
+	assert_eq!(report.modules.len(), output.modules.len());
+	for (entry, module) in report.modules.iter().zip(&output.modules) {
+		assert_eq!(entry.sizes.raw, module.code.len());
+		assert!(entry.sizes.gzip > 0);
+		assert!(entry.sizes.brotli > 0);
+	}
+
+	let raw_total: usize = output.modules.iter().map(|m| m.code.len()).sum();
+	assert_eq!(report.total.raw, raw_total);
+	assert_eq!(report.origins.len(), 1);
+	assert_eq!(report.origins[&Atom::from("test.tsx")], report.total);
+
+	// Both segments belong to App and share the component entry
+	let segments_raw: usize = output
+		.modules
+		.iter()
+		.filter(|m| m.segment.is_some())
+		.map(|m| m.code.len())
+		.sum();
+	assert_eq!(report.components.len(), 1);
+	assert_eq!(
+		report.components[&Atom::from("test.tsx_App_component")].raw,
+		segments_raw
+	);
+	let entry = report
+		.modules
+		.iter()
+		.find(|m| m.segment.is_some())
+		.map(|m| m.entry.clone())
+		.unwrap();
+	assert_eq!(report.entries[&entry].raw, segments_raw);
+
+	// The manifest bundles carry the compressed sizes of their module
+	for bundle in output.get_manifest().bundles.values() {
+		assert!(bundle.gzip_size.is_some_and(|size| size > 0));
+		assert!(bundle.brotli_size.is_some_and(|size| size > 0));
+	}
+}
+
+#[test]
+fn transform_stats_are_opt_in() {
+	let code = r#"
+import { component$, useTask$ } from '@qwik.dev/core';
+import { unused } from './unused';
+
+const label = 'count';
+
+export const App = component$(() => {
+	useTask$(() => {
+		console.log(label);
+	});
+	return <div></div>;
+});
+"#;
+	let options = |stats: bool| TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "test.tsx".into(),
+			dev_path: None,
+		}],
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Prod,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let output = transform_modules(options(false)).unwrap();
+	assert!(output.stats.is_empty());
+
+	let output = transform_modules(options(true)).unwrap();
+	assert_eq!(output.stats.len(), 1);
+	let stats = &output.stats[0];
+	assert_eq!(stats.path, "test.tsx");
+	assert_eq!(stats.segments_created, 2);
+	assert_eq!(stats.vars_migrated, 1);
+	assert!(stats.imports_dropped >= 1);
+
+	let names: Vec<_> = stats.passes.iter().map(|p| p.name.as_str()).collect();
+	for pass in [
+		"parse",
+		"typescript",
+		"react_jsx",
+		"resolver",
+		"qwik_transform",
+		"simplify",
+		"variable_migration",
+		"new_module",
+		"hygiene",
+		"codegen",
+	] {
+		assert!(
+			names.contains(&pass),
+			"missing pass {} in {:?}",
+			pass,
+			names
+		);
+	}
+	let codegen = stats.passes.iter().find(|p| p.name == "codegen").unwrap();
+	assert_eq!(codegen.runs, 3);
+}
+
+#[test]
+fn explain_records_segment_decisions() {
+	let code = r#"
+import { component$, useTask$, useSignal } from '@qwik.dev/core';
+import { format } from './format';
+
+const prefix = 'count';
+const shared = 'shared';
+
+export const App = component$(() => {
+	const count = useSignal(0);
+	const local = 1;
+	useTask$(() => {
+		console.log(prefix, shared, count.value, format(local));
+	});
+	useTask$(() => {
+		console.log(shared);
+	});
+	return <button class="static" title={count.value} onClick$={() => count.value++}></button>;
+});
+"#;
+	let options = |explain: bool| TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "test.tsx".into(),
+			dev_path: None,
+		}],
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let output = transform_modules(options(false)).unwrap();
+	assert!(output.explain.is_empty());
+
+	let output = transform_modules(options(true)).unwrap();
+	assert_eq!(output.explain.len(), 1);
+	let report = &output.explain[0];
+	assert_eq!(report.path, "test.tsx");
+	let decisions = |display_name: &str| {
+		&report
+			.segments
+			.iter()
+			.find(|s| s.display_name.as_ref() == display_name)
+			.unwrap_or_else(|| panic!("missing segment {}", display_name))
+			.decisions
+	};
+
+	let task = decisions("test.tsx_App_component_useTask");
+	for (name, captured, reason) in [
+		("count", true, CaptureReason::DeclaredInScope),
+		("format", false, CaptureReason::Import),
+		("prefix", false, CaptureReason::RootDeclaration),
+		("console", false, CaptureReason::NotInScope),
+	] {
+		let expected = Decision::Capture {
+			name: name.into(),
+			captured,
+			reason,
+		};
+		assert!(task.contains(&expected), "missing {:?}", expected);
+	}
+	assert!(task.contains(&Decision::Migration {
+		name: "prefix".into(),
+		migrated: true,
+		reason: MigrationReason::SingleSegment,
+	}));
+	assert!(task.contains(&Decision::Migration {
+		name: "shared".into(),
+		migrated: false,
+		reason: MigrationReason::UsedByMultipleSegments,
+	}));
+
+	let click = decisions("test.tsx_App_component_button_q_e_click");
+	assert!(click.contains(&Decision::Capture {
+		name: "count".into(),
+		captured: false,
+		reason: CaptureReason::Param,
+	}));
+	assert!(click.contains(&Decision::Hoist {
+		hoisted: true,
+		current_depth: None,
+		target_depth: None,
+		reason: HoistReason::NoCaptures,
+	}));
+
+	let props: Vec<_> = decisions("test.tsx_App_component")
+		.iter()
+		.filter_map(|d| match d {
+			Decision::JsxProp {
+				name,
+				target,
+				reason,
+				location,
+			} => {
+				assert!(location.is_some());
+				Some((name.as_ref(), *target, *reason))
+			}
+			_ => None,
+		})
+		.collect();
+	assert_eq!(
+		props,
+		vec![
+			("class", PropTarget::Const, JsxPropReason::ConstExpression),
+			("title", PropTarget::Const, JsxPropReason::Getter),
+			("onClick$", PropTarget::Const, JsxPropReason::ConstCaptures),
+		]
+	);
+}
+
+#[test]
+fn debug_dump_captures_every_pass() {
+	let code = r#"
+import { component$, useTask$ } from '@qwik.dev/core';
+
+export const App = component$(() => {
+	useTask$(() => {
+		// keep me
+		console.log('task');
+	});
+	return <div></div>;
+});
+"#;
+	let options = |debug_dump: Option<DebugDumpOptions>| TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "test.tsx".into(),
+			dev_path: None,
+		}],
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let plain = transform_modules(options(None)).unwrap();
+	assert!(plain.debug_dumps.is_empty());
+
+	let output = transform_modules(options(Some(DebugDumpOptions {
+		ast: cfg!(feature = "ast-dump"),
+		out_dir: None,
+	})))
+	.unwrap();
+	// Dumping must not change the emitted code, comments included
+	let codes = |output: &TransformOutput| -> Vec<String> {
+		output.modules.iter().map(|m| m.code.clone()).collect()
+	};
+	assert_eq!(codes(&plain), codes(&output));
+	assert!(output.modules.iter().any(|m| m.code.contains("// keep me")));
+
+	let root: Vec<_> = output
+		.debug_dumps
+		.iter()
+		.filter(|d| d.segment.is_none())
+		.collect();
+	let passes: Vec<_> = root.iter().map(|d| d.pass.as_str()).collect();
+	for pass in [
+		"parse",
+		"typescript",
+		"react_jsx",
+		"resolver",
+		"qwik_transform",
+		"hygiene",
+	] {
+		assert!(passes.contains(&pass), "missing {} in {:?}", pass, passes);
+	}
+	assert!(root.iter().enumerate().all(|(i, d)| d.index == i));
+	assert!(root[0].code.contains("component$"));
+	let transformed = root.iter().find(|d| d.pass == "qwik_transform").unwrap();
+	assert!(transformed.code.contains("componentQrl"));
+	if cfg!(feature = "ast-dump") {
+		let ast: serde_json::Value = serde_json::from_str(root[0].ast.as_ref().unwrap()).unwrap();
+		assert_eq!(ast["type"], "Module");
+	} else {
+		let err = transform_modules(options(Some(DebugDumpOptions {
+			ast: true,
+			out_dir: None,
+		})))
+		.unwrap_err();
+		assert!(err.to_string().contains("ast-dump feature"), "{}", err);
+	}
+
+	let task = output
+		.debug_dumps
+		.iter()
+		.find(|d| d.segment.is_some() && d.pass == "new_module" && d.code.contains("task"))
+		.unwrap();
+	assert_eq!(task.index, 0);
+
+	let out_dir = std::env::temp_dir().join(format!("qwik-debug-dump-{}", std::process::id()));
+	let output = transform_modules(options(Some(DebugDumpOptions {
+		ast: false,
+		out_dir: Some(out_dir.to_string_lossy().to_string()),
+	})))
+	.unwrap();
+	assert!(output.debug_dumps.is_empty());
+	let parse_dump = std::fs::read_to_string(out_dir.join("test.tsx").join("00_parse.js")).unwrap();
+	assert!(parse_dump.contains("component$"));
+	assert!(!out_dir.join("test.tsx").join("00_parse.json").exists());
+
+	// Inputs outside of srcDir must not be dumped outside of outDir
+	let output = transform_modules(TransformModulesOptions {
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "../escape.tsx".into(),
+			dev_path: None,
+		}],
+		..options(Some(DebugDumpOptions {
+			ast: false,
+			out_dir: Some(out_dir.to_string_lossy().to_string()),
+		}))
+	})
+	.unwrap();
+	assert!(output
+		.diagnostics
+		.iter()
+		.any(|d| d.message.contains("outside of outDir")));
+	assert!(!out_dir.join("..").join("escape.tsx").exists());
+	std::fs::remove_dir_all(&out_dir).unwrap();
+}
+
+#[test]
+fn custom_passes_run_at_their_points() {
+	use std::sync::{Arc, Mutex};
+	use swc_ecmascript::ast;
+	use swc_ecmascript::visit::{Fold, VisitMut};
+
+	struct ReplaceStr;
+	impl VisitMut for ReplaceStr {
+		fn visit_mut_str(&mut self, node: &mut ast::Str) {
+			if node.value == *"before" {
+				*node = ast::Str::from("after");
+			}
+		}
+	}
+
+	struct RenameConsole;
+	impl Fold for RenameConsole {
+		fn fold_ident(&mut self, node: ast::Ident) -> ast::Ident {
+			if node.sym == *"console" {
+				ast::Ident::new("logger".into(), node.span, node.ctxt)
+			} else {
+				node
+			}
+		}
+	}
+
+	let code = r#"
+import { component$, useTask$ } from '@qwik.dev/core';
+
+export const App = component$(() => {
+	useTask$(() => {
+		console.log('before');
+	});
+	return <div></div>;
+});
+"#;
+	type Seen = Vec<(PassPoint, Option<String>)>;
+	let seen: Arc<Mutex<Seen>> = Arc::default();
+	let mut custom_passes = CustomPasses::new();
+	for point in [
+		PassPoint::BeforeTypescript,
+		PassPoint::BeforeQwikTransform,
+		PassPoint::AfterSegmentation,
+		PassPoint::Segment,
+	] {
+		let seen = Arc::clone(&seen);
+		custom_passes.add(point, move |_, ctx| {
+			assert_eq!(ctx.path, "test.tsx");
+			seen.lock()
+				.unwrap()
+				.push((point, ctx.segment.map(|s| s.to_string())));
+		});
+	}
+	custom_passes
+		.add_visit_mut(PassPoint::BeforeQwikTransform, |_| ReplaceStr)
+		.add_fold(PassPoint::Segment, |_| RenameConsole);
+
+	let output = transform_modules(TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![TransformModuleInput {
+			code: code.into(),
+			path: "test.tsx".into(),
+			dev_path: None,
+		}],
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes,
+	})
+	.unwrap();
+
+	let task = output
+		.modules
+		.iter()
+		.find(|m| {
+			m.segment
+				.as_ref()
+				.is_some_and(|s| s.ctx_name.as_ref() == "useTask$")
+		})
+		.unwrap();
+	assert!(task.code.contains("logger.log(\"after\")"), "{}", task.code);
+
+	let seen = std::mem::take(&mut *seen.lock().unwrap());
+	let points: Vec<_> = seen.iter().map(|(point, _)| *point).collect();
+	assert_eq!(
+		&points[..3],
+		&[
+			PassPoint::BeforeTypescript,
+			PassPoint::BeforeQwikTransform,
+			PassPoint::AfterSegmentation,
+		]
+	);
+	let segments: Vec<_> = seen
+		.iter()
+		.filter(|(point, _)| *point == PassPoint::Segment)
+		.map(|(_, segment)| segment.clone().unwrap())
+		.collect();
+	assert_eq!(segments.len(), 2);
+	assert!(segments.contains(&task.segment.as_ref().unwrap().name.to_string()));
+}
+
+#[test]
+fn transform_programs_matches_transform_modules() {
+	use swc_common::comments::SingleThreadedComments;
+	use swc_common::{sync::Lrc, FileName, SourceMap};
+	use swc_ecmascript::parser::lexer::Lexer;
+	use swc_ecmascript::parser::{Parser, StringInput, Syntax, TsSyntax};
+
+	let code = r#"
+import { component$, useTask$ } from '@qwik.dev/core';
+
+export const App = component$(() => {
+	useTask$(() => {
+		/* comment */
+		console.log('task');
+	});
+	return <div></div>;
+});
+"#;
+	let options = |input: Vec<TransformModuleInput>| TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input,
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let expected = transform_modules(options(vec![TransformModuleInput {
+		code: code.into(),
+		path: "test.tsx".into(),
+		dev_path: None,
+	}]))
+	.unwrap();
+
+	let source_map: Lrc<SourceMap> = Default::default();
+	let source_file = source_map.new_source_file(
+		FileName::Real("/user/qwik/src/test.tsx".into()).into(),
+		code.to_string(),
+	);
+	let comments = SingleThreadedComments::default();
+	let lexer = Lexer::new(
+		Syntax::Typescript(TsSyntax {
+			tsx: true,
+			..Default::default()
+		}),
+		Default::default(),
+		StringInput::from(&*source_file),
+		Some(&comments),
+	);
+	let program = Parser::new_from(lexer).parse_program().unwrap();
+
+	let output = transform_programs(
+		options(vec![]),
+		vec![TransformProgramInput {
+			path: "test.tsx".into(),
+			dev_path: None,
+			program,
+			source_map,
+			comments,
+		}],
+	)
+	.unwrap();
+
+	assert!(output.diagnostics.is_empty());
+	assert_eq!(output.modules.len(), expected.modules.len());
+	for (module, expected) in output.modules.iter().zip(&expected.modules) {
+		assert_eq!(module.path, expected.path);
+		assert_eq!(module.code, expected.code);
+		assert!(expected.program.is_none());
+		assert_eq!(module.program.is_some(), module.segment.is_none());
+	}
+}
+
+#[test]
+fn analyze_modules_matches_transform_modules() {
+	let options = || TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![TransformModuleInput {
+			code: r#"
+import { component$, useTask$ } from '@qwik.dev/core';
+import { format } from './utils';
+
+export const App = component$(() => {
+	useTask$(() => {
+		console.log(format('task'));
+	});
+	return <button onClick$={() => import('./lazy')}></button>;
+});
+export { App as Main };
+export default function Root() {
+	return <App />;
+}
+"#
+			.into(),
+			path: "test.tsx".into(),
+			dev_path: None,
+		}],
+		source_maps: false,
+		minify: MinifyMode::Simplify,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let transformed = transform_modules(options()).unwrap();
+	let analyzed = analyze_modules(options()).unwrap();
+
+	assert!(analyzed.diagnostics.is_empty());
+	assert_eq!(analyzed.modules.len(), 1);
+	let module = &analyzed.modules[0];
+	assert_eq!(module.path, "test.tsx");
+	assert_eq!(module.exports, vec!["App", "Main", "default"]);
+	let root = transformed
+		.modules
+		.iter()
+		.find(|m| m.segment.is_none())
+		.unwrap();
+	assert_eq!(module.imports, root.imports);
+	assert!(module.imports.contains(&"./lazy".into()));
+
+	let expected: Vec<_> = transformed
+		.modules
+		.iter()
+		.filter_map(|m| m.segment.clone())
+		.map(|segment| SegmentAnalysis { size: 0, ..segment })
+		.collect();
+	assert_eq!(module.segments.len(), 3);
+	for segment in &expected {
+		assert!(module.segments.contains(segment), "{}", segment.name);
+	}
+}
+
+#[test]
+fn module_info_lists_imports_and_exports() {
+	let output = module_info(TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![
+			TransformModuleInput {
+				code: r#"
+import { component$ as c$ } from '@qwik.dev/core';
+import Layout, * as utils from './utils';
+export * from './shared';
+export { helper } from './helper';
+
+const local = 1;
+function internal() {}
+export const Page = c$(() => import('./lazy'));
+export { local as renamed, Page as Main };
+export default () => null;
+"#
+				.into(),
+				path: "routes/index.tsx".into(),
+				dev_path: None,
+			},
+			TransformModuleInput {
+				code: "export const = ;".into(),
+				path: "broken.ts".into(),
+				dev_path: None,
+			},
+		],
+		source_maps: false,
+		minify: MinifyMode::None,
+		transpile_ts: false,
+		transpile_jsx: false,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: None,
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	})
+	.unwrap();
+
+	assert_eq!(output.modules.len(), 1);
+	assert_eq!(output.diagnostics.len(), 1);
+	assert_eq!(output.diagnostics[0].file, "broken.ts");
+
+	let info = &output.modules[0];
+	assert_eq!(info.path, "routes/index.tsx");
+	assert_eq!(
+		info.imports,
+		vec![
+			ModuleImport {
+				local: "c$".into(),
+				source: "@qwik.dev/core".into(),
+				specifier: "component$".into(),
+				kind: ImportKind::Named,
+			},
+			ModuleImport {
+				local: "Layout".into(),
+				source: "./utils".into(),
+				specifier: "default".into(),
+				kind: ImportKind::Default,
+			},
+			ModuleImport {
+				local: "utils".into(),
+				source: "./utils".into(),
+				specifier: "*".into(),
+				kind: ImportKind::All,
+			},
+		]
+	);
+	assert_eq!(info.dynamic_imports, vec!["./lazy"]);
+	assert_eq!(
+		info.exports,
+		vec![
+			ModuleExport {
+				local: Some("Page".into()),
+				exported: vec!["Page".into(), "Main".into()],
+			},
+			ModuleExport {
+				local: Some("local".into()),
+				exported: vec!["renamed".into()],
+			},
+			ModuleExport {
+				local: None,
+				exported: vec!["default".into()],
+			},
+		]
+	);
+	assert_eq!(info.reexports, vec!["./shared", "./helper"]);
+	// `local` is exported through `export { local as renamed }`
+	assert_eq!(info.root_declarations, vec!["internal"]);
+}
+
+#[test]
+fn panicking_input_becomes_a_diagnostic() {
+	let mut custom_passes = CustomPasses::new();
+	custom_passes.add(PassPoint::BeforeTypescript, |_, ctx| {
+		assert!(ctx.path != "crash.tsx", "boom");
+	});
+	let input = |path: &str| TransformModuleInput {
+		code: "export const a = 1;".into(),
+		path: path.into(),
+		dev_path: None,
+	};
+	let output = transform_modules(TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![input("before.tsx"), input("crash.tsx"), input("after.tsx")],
+		source_maps: false,
+		minify: MinifyMode::None,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module: None,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes,
+	})
+	.unwrap();
+
+	let mut paths: Vec<_> = output.modules.iter().map(|m| m.path.as_str()).collect();
+	paths.sort_unstable();
+	assert_eq!(paths, vec!["after.js", "before.js"]);
+	assert_eq!(output.diagnostics.len(), 1);
+	let diagnostic = &output.diagnostics[0];
+	assert_eq!(diagnostic.file, "crash.tsx");
+	assert_eq!(diagnostic.category, DiagnosticCategory::Error);
+	assert_eq!(diagnostic.code.as_deref(), Some("optimizer-panic"));
+	assert!(diagnostic.message.contains("boom"));
+}
+
+#[test]
+fn per_file_errors_become_diagnostics() {
+	let input = |path: &str| TransformModuleInput {
+		code: "export const a = 1;".into(),
+		path: path.into(),
+		dev_path: None,
+	};
+	let options = |core_module: Option<String>| TransformModulesOptions {
+		src_dir: "/user/qwik/src/".into(),
+		root_dir: None,
+		input: vec![
+			input("before.tsx"),
+			input("no-extension"),
+			input("after.tsx"),
+		],
+		source_maps: false,
+		minify: MinifyMode::None,
+		transpile_ts: true,
+		transpile_jsx: true,
+		preserve_filenames: false,
+		entry_strategy: EntryStrategy::Segment,
+		explicit_extensions: false,
+		mode: EmitMode::Test,
+		scope: None,
+		core_module,
+		strip_exports: None,
+		strip_ctx_name: None,
+		strip_event_handlers: false,
+		reg_ctx_name: None,
+		is_server: Some(true),
+		stats: false,
+		explain: false,
+		manual_chunks: None,
+		size_report: false,
+		debug_dump: None,
+		verify: false,
+		rules: Default::default(),
+		custom_passes: Default::default(),
+	};
+
+	let output = transform_modules(options(None)).unwrap();
+	let mut paths: Vec<_> = output.modules.iter().map(|m| m.path.as_str()).collect();
+	paths.sort_unstable();
+	assert_eq!(paths, vec!["after.js", "before.js"]);
+	assert_eq!(output.diagnostics.len(), 1);
+	let diagnostic = &output.diagnostics[0];
+	assert_eq!(diagnostic.file, "no-extension");
+	assert_eq!(diagnostic.category, DiagnosticCategory::Error);
+	assert_eq!(diagnostic.code.as_deref(), Some("invalid-path"));
+
+	let err = transform_modules(options(Some(String::new()))).unwrap_err();
+	assert!(matches!(err, OptimizerError::InvalidOptions { .. }));
+	assert_eq!(err.file(), None);
+}
+
+#[test]
+fn options_defaults_and_unknown_fields() {
+	let options: TransformModulesOptions =
+		serde_json::from_str(r#"{ "srcDir": "/user/qwik/src/" }"#).unwrap();
+	assert_eq!(options.mode, EmitMode::Lib);
+	assert_eq!(options.minify, MinifyMode::Simplify);
+	assert!(matches!(options.entry_strategy, EntryStrategy::Smart));
+	assert!(options.input.is_empty());
+
+	let err = serde_json::from_str::<TransformModulesOptions>(
+		r#"{ "srcDir": "/user/qwik/src/", "stripCtxNames": ["server"] }"#,
+	)
+	.unwrap_err();
+	assert!(err.to_string().contains("unknown field `stripCtxNames`"));
+	assert!(serde_json::from_str::<TransformModulesOptions>("{}").is_err());
+}
+
+#[test]
+fn options_builder_validates() {
+	let options = TransformModulesOptions::builder("/user/qwik/src/")
+		.input("test.tsx", "export const a = 1;")
+		.mode(EmitMode::Prod)
+		.strip_exports(["onGet"])
+		.build()
+		.unwrap();
+	assert_eq!(options.strip_exports, Some(vec![Atom::from("onGet")]));
+
+	let err = TransformModulesOptions::builder("/user/qwik/src/")
+		.input("test.tsx", "")
+		.input("test.tsx", "")
+		.mode(EmitMode::Lib)
+		.strip_exports(["onGet"])
+		.build()
+		.unwrap_err();
+	assert_eq!(
+		err,
+		OptimizerError::InvalidOptions {
+			message:
+				"stripExports can't be used in lib mode, input test.tsx is listed more than once"
+					.into()
+		}
+	);
+}
+
+#[test]
+fn options_schema_is_up_to_date() {
+	let schema = to_string_pretty(&options_schema()).unwrap();
+	assert_eq!(
+		schema.trim(),
+		include_str!("../../options.schema.json").trim(),
+		"Regenerate it with `cargo run -p qwik-optimizer -- schema > packages/optimizer/options.schema.json`"
+	);
+}
+
+#[test]
+fn verify_reports_broken_segments() {
+	use swc_ecmascript::visit::{VisitMut, VisitMutWith};
+
+	/// The messages of the diagnostics `verify` reports once `pass` has run at `point`.
+	fn verify_messages(
+		point: PassPoint,
+		pass: impl Fn(&mut ast::Program) + Send + Sync + 'static,
+	) -> Vec<String> {
+		let mut custom_passes = CustomPasses::new();
+		custom_passes.add(point, move |program, _| pass(program));
+		let options = TransformModulesOptions::builder("/user/qwik/src/")
+			.input(
+				"test.tsx",
+				r#"
+import { $ } from '@qwik.dev/core';
+export const counter = (count) => $(() => count + 1);
+"#,
+			)
+			.mode(EmitMode::Test)
+			.transpile_ts(true)
+			.verify(true)
+			.custom_passes(custom_passes)
+			.build()
+			.unwrap();
+		let output = transform_modules(options).unwrap();
+		for diagnostic in &output.diagnostics {
+			assert_eq!(diagnostic.code.as_deref(), Some("internal-error"));
+			assert_eq!(diagnostic.file, "test.tsx");
+		}
+		output.diagnostics.into_iter().map(|d| d.message).collect()
+	}
+
+	assert!(verify_messages(PassPoint::Segment, |_| {}).is_empty());
+
+	// Simulates a transform bug that loses the imports of every segment
+	let messages = verify_messages(PassPoint::Segment, |program| {
+		if let ast::Program::Module(module) = program {
+			module.body.retain(|item| {
+				!matches!(
+					item,
+					ast::ModuleItem::ModuleDecl(ast::ModuleDecl::Import(_))
+				)
+			});
+		}
+	});
+	assert_eq!(messages.len(), 1);
+	assert!(messages[0].contains("references `_captures`, which is not declared or imported"));
+
+	// `_captures[0]` becomes `_captures[1]`, the segment only captures `count`
+	struct ShiftCaptures;
+	impl VisitMut for ShiftCaptures {
+		fn visit_mut_member_expr(&mut self, member: &mut ast::MemberExpr) {
+			if let ast::MemberProp::Computed(prop) = &mut member.prop {
+				if let ast::Expr::Lit(ast::Lit::Num(index)) = &mut *prop.expr {
+					index.value += 1.0;
+					index.raw = None;
+				}
+			}
+			member.visit_mut_children_with(self);
+		}
+	}
+	let messages = verify_messages(PassPoint::Segment, |program| {
+		program.visit_mut_with(&mut ShiftCaptures)
+	});
+	assert_eq!(messages.len(), 1);
+	assert!(messages[0].contains("reads a capture past the 1 it was given"));
+
+	// `.w([count])` becomes `.w([count, count])`
+	struct ExtraCapture;
+	impl VisitMut for ExtraCapture {
+		fn visit_mut_call_expr(&mut self, call: &mut ast::CallExpr) {
+			if let Some(ast::Expr::Array(array)) = call.args.first_mut().map(|arg| &mut *arg.expr) {
+				if let Some(first) = array.elems.first().cloned() {
+					array.elems.push(first);
+				}
+			}
+			call.visit_mut_children_with(self);
+		}
+	}
+	let messages = verify_messages(PassPoint::AfterSegmentation, |program| {
+		program.visit_mut_with(&mut ExtraCapture)
+	});
+	assert_eq!(messages.len(), 1, "{:?}", messages);
+	assert!(messages[0].contains("passes 2 captures to"));
+	assert!(messages[0].contains("which reads 1"));
+
+	// `qrl(() => import("./test.tsx_counter_..."))` loads a module that was never emitted
+	struct MissingImport;
+	impl VisitMut for MissingImport {
+		fn visit_mut_call_expr(&mut self, call: &mut ast::CallExpr) {
+			// The transform emits `import` as an identifier callee, which parses back as `Callee::Import`
+			let is_import = match &call.callee {
+				ast::Callee::Import(_) => true,
+				ast::Callee::Expr(callee) => {
+					matches!(&**callee, ast::Expr::Ident(ident) if ident.sym == "import")
+				}
+				_ => false,
+			};
+			if is_import {
+				call.args[0].expr = Box::new(ast::Expr::Lit(ast::Lit::Str("./missing".into())));
+			}
+			call.visit_mut_children_with(self);
+		}
+	}
+	let messages = verify_messages(PassPoint::AfterSegmentation, |program| {
+		program.visit_mut_with(&mut MissingImport)
+	});
+	assert_eq!(messages.len(), 1, "{:?}", messages);
+	assert!(messages[0].contains("loads a QRL from ./missing, which was not emitted"));
+}
+
+#[test]
+fn rule_severities_override_emitted_category() {
+	let code = r#"
+import { $, component$ } from '@qwik.dev/core';
+
+export const App = component$(() => {
+	function hola() {}
+	return (
+		<button passive:click preventdefault:click onClick$={() => hola()}>
+			click
+		</button>
+	);
+});
+"#;
+	let transform = |rules: &[(&str, RuleSeverity)]| {
+		let builder = TransformModulesOptions::builder("/user/qwik/src/")
+			.input("test.tsx", code)
+			.mode(EmitMode::Test)
+			.transpile_ts(true)
+			.transpile_jsx(true);
+		let options = rules
+			.iter()
+			.fold(builder, |builder, (code, severity)| {
+				builder.rule(*code, *severity)
+			})
+			.build()
+			.unwrap();
+		let mut categories: Vec<_> = transform_modules(options)
+			.unwrap()
+			.diagnostics
+			.into_iter()
+			.map(|d| (d.code.unwrap(), d.category))
+			.collect();
+		categories.sort_by(|a, b| a.0.cmp(&b.0));
+		categories
+	};
+
+	assert_eq!(
+		transform(&[]),
+		vec![
+			("C02".to_string(), DiagnosticCategory::Error),
+			(
+				"preventdefault-passive-check".to_string(),
+				DiagnosticCategory::Warning
+			),
+		]
+	);
+	assert_eq!(
+		transform(&[
+			("C02", RuleSeverity::Warn),
+			("preventdefault-passive-check", RuleSeverity::Error),
+		]),
+		vec![
+			("C02".to_string(), DiagnosticCategory::Warning),
+			(
+				"preventdefault-passive-check".to_string(),
+				DiagnosticCategory::Error
+			),
+		]
+	);
+	assert_eq!(
+		transform(&[("C02", RuleSeverity::Off)]),
+		vec![(
+			"preventdefault-passive-check".to_string(),
+			DiagnosticCategory::Warning
+		)]
+	);
+
+	let err = TransformModulesOptions::builder("/user/qwik/src/")
+		.rule("C99", RuleSeverity::Off)
+		.build()
+		.unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"Invalid options: unknown rule C99 in rules"
+	);
+
+	let err = TransformModulesOptions::builder("/user/qwik/src/")
+		.rule("internal-error", RuleSeverity::Off)
+		.build()
+		.unwrap_err();
+	assert_eq!(
+		err.to_string(),
+		"Invalid options: unknown rule internal-error in rules"
+	);
+}
+
+#[test]
+fn suppression_directives_ranges_files_and_wildcards() {
+	let diagnostics = |body: &str| {
+		let code = format!(
+			r#"
+import {{ component$ }} from '@qwik.dev/core';
+{}
+export const App = component$(() => {{
+	return (
+		<div>
+			{}
+		</div>
+	);
+}});
+"#,
+			body.split_once('|').map_or("", |(top, _)| top),
+			body.split_once('|').map_or(body, |(_, jsx)| jsx),
+		);
+		let options = TransformModulesOptions::builder("/user/qwik/src/")
+			.input("test.tsx", code)
+			.mode(EmitMode::Test)
+			.transpile_ts(true)
+			.transpile_jsx(true)
+			.build()
+			.unwrap();
+		transform_modules(options)
+			.unwrap()
+			.diagnostics
+			.into_iter()
+			.map(|d| {
+				let line = d.highlights.as_ref().map_or(0, |h| h[0].start_line);
+				(d.code.unwrap(), line)
+			})
+			.collect::<Vec<_>>()
+	};
+	let button = "<button passive:click preventdefault:click onClick$={() => {}} />";
+	let passive = "preventdefault-passive-check".to_string();
+
+	// Lines 7 to 11 hold the JSX children below
+	let range = format!(
+		"|{{/* @qwik-disable preventdefault-* */}}\n{button}\n{{/* @qwik-enable preventdefault-* */}}\n{button}\n{button}"
+	);
+	assert_eq!(
+		diagnostics(&range),
+		vec![(passive.clone(), 10), (passive, 11)]
+	);
+
+	let unclosed = format!("|{{/* @qwik-disable * */}}\n{button}\n{button}");
+	assert_eq!(diagnostics(&unclosed), vec![]);
+
+	let file = format!("/* @qwik-disable-file preventdefault-passive-check */|{button}\n{button}");
+	assert_eq!(diagnostics(&file), vec![]);
+
+	let unused = format!(
+		"/* @qwik-disable-file C02 */|{{/* @qwik-disable-next-line preventdefault-passive-check */}}\n{button}"
+	);
+	assert_eq!(
+		diagnostics(&unused),
+		vec![("unused-suppression".to_string(), 3)]
+	);
+
+	let silenced = format!(
+		"/* @qwik-disable-file C02, unused-suppression */|{{/* @qwik-disable-next-line preventdefault-passive-check */}}\n{button}"
+	);
+	assert_eq!(diagnostics(&silenced), vec![]);
+
+	let alone = format!("/* @qwik-disable-file unused-suppression */|{button}");
+	assert_eq!(
+		diagnostics(&alone),
+		vec![
+			("preventdefault-passive-check".to_string(), 7),
+			("unused-suppression".to_string(), 3)
+		]
+	);
+
+	let enable = format!(
+		"|{{/* @qwik-disable preventdefault-* */}}\n{button}\n{{/* @qwik-enable preventdefault-passive-check */}}\n{button}"
+	);
+	assert_eq!(
+		diagnostics(&enable),
+		vec![("preventdefault-passive-check".to_string(), 10)]
+	);
+}
+
+#[test]
+fn diagnostics_carry_applicable_fixes() {
+	let fixes = |code: &str| {
+		let options = TransformModulesOptions::builder("/user/qwik/src/")
+			.input("test.tsx", code)
+			.mode(EmitMode::Test)
+			.transpile_ts(true)
+			.transpile_jsx(true)
+			.build()
+			.unwrap();
+		transform_modules(options)
+			.unwrap()
+			.diagnostics
+			.into_iter()
+			.flat_map(|d| d.fixes)
+			.collect::<Vec<_>>()
+	};
+
+	let passive = r#"import { component$ } from '@qwik.dev/core';
+export const App = component$(() => {
+	return <button passive:click preventdefault:click={true} onClick$={() => {}} />;
+});
+"#;
+	let passive_fixes = fixes(passive);
+	assert!(passive_fixes.iter().all(|fix| fix.safe));
+	assert_eq!(
+		apply_fixes(passive, &passive_fixes),
+		(passive.replace(" preventdefault:click={true}", ""), 1)
+	);
+	for attr in [
+		" preventdefault:click",
+		" preventdefault:click={/* } */ `}>`.length > 0}",
+	] {
+		let code = passive.replace(" preventdefault:click={true}", attr);
+		assert_eq!(
+			apply_fixes(&code, &fixes(&code)),
+			(passive.replace(" preventdefault:click={true}", ""), 1),
+			"{}",
+			attr
+		);
+	}
+
+	let captured = r#"import { component$ } from '@qwik.dev/core';
+export const App = component$(() => {
+	function greet() {}
+	return <button onClick$={() => greet()} />;
+});
+"#;
+	let captured_fixes = fixes(captured);
+	assert_eq!(captured_fixes.len(), 1);
+	assert!(!captured_fixes[0].safe);
+	assert_eq!(
+		apply_fixes(captured, &captured_fixes).0,
+		format!(
+			"import {{ $ }} from \"@qwik.dev/core\";\n{}",
+			captured.replace(
+				"function greet() {}",
+				"const greet = $(function greet() {});"
+			)
+		)
+	);
+}
+
+#[test]
+fn diagnostics_render_as_code_frames_and_sarif() {
+	let code = "import { component$ } from '@qwik.dev/core';\nexport const App = component$(() => {\n\treturn <button passive:click preventdefault:click onClick$={() => {}} />;\n});\n";
+	let options = TransformModulesOptions::builder("/user/qwik/src/")
+		.input("test.tsx", code)
+		.mode(EmitMode::Test)
+		.transpile_ts(true)
+		.transpile_jsx(true)
+		.build()
+		.unwrap();
+	let diagnostics = transform_modules(options).unwrap().diagnostics;
+	let inputs = [TransformModuleInput {
+		path: "test.tsx".into(),
+		dev_path: None,
+		code: code.into(),
+	}];
+
+	let frames = to_code_frames(&diagnostics, &inputs);
+	assert!(frames
+		.starts_with("warning[preventdefault-passive-check]: preventdefault:click has no effect"));
+	assert!(frames.contains(
+		" --> test.tsx:3:9\n  |\n3 | \treturn <button passive:click preventdefault:click onClick$={() => {}} />;\n  | \t       ^^^^^^^"
+	));
+	assert!(frames.contains("  = help: Remove preventdefault:click\n"));
+
+	let sarif = to_sarif(&diagnostics, &inputs, "/user/qwik/src");
+	assert_eq!(sarif["version"], "2.1.0");
+	assert_eq!(
+		sarif["runs"][0]["originalUriBaseIds"]["SRCROOT"]["uri"],
+		"file:///user/qwik/src/"
+	);
+	let result = &sarif["runs"][0]["results"][0];
+	assert_eq!(result["ruleId"], "preventdefault-passive-check");
+	assert_eq!(result["level"], "warning");
+	let artifact = &result["locations"][0]["physicalLocation"]["artifactLocation"];
+	assert_eq!(
+		(&artifact["uri"], &artifact["uriBaseId"]),
+		(&"test.tsx".into(), &"SRCROOT".into())
+	);
+	let region = &result["locations"][0]["physicalLocation"]["region"];
+	assert_eq!(
+		(&region["startLine"], &region["startColumn"]),
+		(&3.into(), &9.into())
+	);
+	let replacement = &result["fixes"][0]["artifactChanges"][0]["replacements"][0];
+	let lo = replacement["deletedRegion"]["byteOffset"].as_u64().unwrap() as usize;
+	let len = replacement["deletedRegion"]["byteLength"].as_u64().unwrap() as usize;
+	assert_eq!(&code[lo..lo + len], " preventdefault:click");
+	assert_eq!(
+		sarif["runs"][0]["tool"]["driver"]["rules"],
+		serde_json::json!([{ "id": "preventdefault-passive-check" }])
+	);
+
+	let windows = to_sarif(&diagnostics, &inputs, r"\\?\C:\Users\my app\src");
+	assert_eq!(
+		windows["runs"][0]["originalUriBaseIds"]["SRCROOT"]["uri"],
+		"file:///C:/Users/my%20app/src/"
+	);
+	let relative = to_sarif(&diagnostics, &inputs, "./src");
+	assert!(relative["runs"][0]["originalUriBaseIds"]["SRCROOT"]["uri"].is_null());
+}
+
+/// The `(line, message)` of every `rule` diagnostic of `code` in `test.tsx`, by line.
+fn lint_findings(code: &str, rule: &str) -> Vec<(usize, String)> {
+	lint_findings_with(code, rule, |builder| builder)
+}
+
+/// Like `lint_findings`, with the options `configure` sets on top of the default ones.
+fn lint_findings_with(
+	code: &str,
+	rule: &str,
+	configure: impl FnOnce(TransformModulesOptionsBuilder) -> TransformModulesOptionsBuilder,
+) -> Vec<(usize, String)> {
+	let mut findings: Vec<_> = lint_diagnostics(code, |code| code == rule, configure)
+		.into_iter()
+		.map(|(line, d)| (line, d.message))
+		.collect();
+	findings.sort();
+	findings
+}
+
+/// The start line and diagnostic of every diagnostic of `code` in `test.tsx` whose code
+/// `matches`, with the options `configure` sets on top of the default ones.
+fn lint_diagnostics(
+	code: &str,
+	matches: impl Fn(&str) -> bool,
+	configure: impl FnOnce(TransformModulesOptionsBuilder) -> TransformModulesOptionsBuilder,
+) -> Vec<(usize, Diagnostic)> {
+	let builder = TransformModulesOptions::builder("/user/qwik/src/")
+		.input("test.tsx", code)
+		.mode(EmitMode::Test)
+		.transpile_ts(true)
+		.transpile_jsx(true);
+	let options = configure(builder).build().unwrap();
+	transform_modules(options)
+		.unwrap()
+		.diagnostics
+		.into_iter()
+		.filter(|d| d.code.as_deref().is_some_and(&matches))
+		.map(|d| (d.highlights.as_ref().unwrap()[0].start_line, d))
+		.collect()
+}
+
+#[test]
+fn rules_of_hooks_lint() {
+	let code = r#"import { component$, useSignal, useTask$ } from '@qwik.dev/core';
+export const useCounter = () => {
+	const count = useSignal(0);
+	return count;
+};
+export const App = component$((props) => {
+	const count = useCounter();
+	if (props.show) {
+		useSignal(1);
+	}
+	for (const item of props.items) {
+		useSignal(item);
+	}
+	const open = props.open && useSignal(true);
+	function nested() {
+		useSignal(2);
+	}
+	useTask$(() => {
+		useSignal(3);
+	});
+	// @qwik-disable-next-line rules-of-hooks
+	const allowed = props.show ? useSignal(4) : undefined;
+	return <button onClick$={() => useSignal(5)}>{count.value}</button>;
+});
+export const factory = () => useSignal(6);
+useSignal(7);
+"#;
+	let findings: Vec<_> = lint_findings(code, "rules-of-hooks")
+		.into_iter()
+		.map(|(line, message)| (line, message.split(", ").next().unwrap().to_string()))
+		.collect();
+	assert_eq!(
+		findings,
+		vec![
+			(9, "useSignal is called conditionally".to_string()),
+			(12, "useSignal is called in a loop".to_string()),
+			(14, "useSignal is called conditionally".to_string()),
+			(
+				16,
+				"useSignal is called inside a nested function".to_string()
+			),
+			(19, "useSignal is called inside a QRL callback".to_string()),
+			(23, "useSignal is called inside a QRL callback".to_string()),
+			(
+				26,
+				"useSignal is called outside of a component$".to_string()
+			),
+		]
+	);
+}
+
+#[test]
+fn jsx_key_lint() {
+	let code = r#"import { component$ } from '@qwik.dev/core';
+export const App = component$((props) => {
+	return (
+		<ul>
+			{props.items.map((item) => <li>{item.name}</li>)}
+			{props.items.map((item, i) => <li key={i}>{item.name}</li>)}
+			{props.items.map((item, i) => <li key={`${i}`}>{item.name}</li>)}
+			{props.items.map((item, i) => <li key={item.id}>{i}</li>)}
+			{props.items.map(function (item) {
+				if (item.hidden) {
+					return null;
+				}
+				return item.link ? <li key={item.id}><a /></li> : <li>{item.name}</li>;
+			})}
+			{props.items.map((item) => <li {...item.attrs} />)}
+			{props.items.flatMap((item) => [<li key={item.id} />])}
+			{props.items.reduce((acc, item) => <>{acc}{item.name}</>, null)}
+			{/* @qwik-disable-next-line jsx-key */}
+			{props.items.map((item) => <li>{item.name}</li>)}
+		</ul>
+	);
+});
+"#;
+	let findings = |transpile_jsx| {
+		lint_findings_with(code, "jsx-key", |builder| {
+			builder.transpile_jsx(transpile_jsx)
+		})
+		.into_iter()
+		.map(|(line, message)| {
+			let start = message.split(' ').take(3).collect::<Vec<_>>().join(" ");
+			(line, start)
+		})
+		.collect::<Vec<_>>()
+	};
+	let expected = vec![
+		(5, "JSX returned from".to_string()),
+		(6, "Index parameter i".to_string()),
+		(7, "Index parameter i".to_string()),
+		(13, "JSX returned from".to_string()),
+	];
+	assert_eq!(findings(true), expected);
+	assert_eq!(findings(false), expected);
+}
+
+#[test]
+fn stale_capture_lint() {
+	let code = r#"import { component$, $ } from '@qwik.dev/core';
+export const App = component$(() => {
+	let count = 0;
+	let label = 'a';
+	let fixed = 1;
+	let local = 0;
+	fixed = 2;
+	const log = $(() => console.log(count, label, fixed));
+	count++;
+	[label] = ['b'];
+	const inc = $(() => {
+		local++;
+		return local;
+	});
+	// @qwik-disable-next-line stale-capture
+	const quiet = $(() => count);
+	count = 10;
+	let i = 0;
+	let j = 0;
+	const rows = [];
+	while (i < 3) {
+		rows.push($(() => i));
+		i++;
+	}
+	i = 0;
+	do {
+		rows.push($(() => j));
+		j++;
+	} while (j < 3);
+	return <button onClick$={() => console.log(count, log, inc, quiet, rows)} />;
+});
+"#;
+	let findings: Vec<_> = lint_findings(code, "stale-capture")
+		.into_iter()
+		.map(|(line, message)| (line, message.split(" after").next().unwrap().to_string()))
+		.collect();
+	assert_eq!(
+		findings,
+		vec![
+			(
+				8,
+				"count is captured by this QRL but assigned again on line 9".to_string()
+			),
+			(
+				8,
+				"label is captured by this QRL but assigned again on line 10".to_string()
+			),
+			(
+				22,
+				"i is captured by this QRL but assigned again on line 25".to_string()
+			),
+		]
+	);
+}
+
+#[test]
+fn non_serializable_capture_lint() {
+	let code = r#"import { component$, useSignal, useTask$, noSerialize } from '@qwik.dev/core';
+class Chart {}
+export const App = component$(() => {
+	const input = useSignal();
+	const node = input.value;
+	const body = document.querySelector('body');
+	const chart = new Chart();
+	const date = new Date();
+	const pending = fetch('/api');
+	const handlers = new Map([['click', () => {}]]);
+	const names = new Set(['a']);
+	const format = (value) => `${value}`;
+	const wrapped = noSerialize(new Chart());
+	useTask$(() => {
+		console.log(node, body, chart, date, pending, handlers, names, format, wrapped);
+	});
+	return <input ref={input} />;
+});
+"#;
+	let findings: Vec<_> = lint_findings(code, "non-serializable-capture")
+		.into_iter()
+		.map(|(_, message)| message.split(", which").next().unwrap().to_string())
+		.collect();
+	assert_eq!(
+		findings,
+		vec![
+			"body is captured by this QRL but holds a DOM node (document.querySelector())",
+			"chart is captured by this QRL but holds a class instance (new Chart())",
+			"format is captured by this QRL but holds a function",
+			"handlers is captured by this QRL but holds a Map with function values",
+			"node is captured by this QRL but holds a DOM node from a ref",
+			"pending is captured by this QRL but holds a Promise (fetch())",
+		]
+	);
+}
+
+#[test]
+fn a11y_lint_rules_are_opt_in() {
+	let code = r#"import { component$ } from '@qwik.dev/core';
+export const App = component$(() => {
+	return (
+		<div>
+			<img src="/logo.svg" />
+			<img src="/spacer.gif" alt="" />
+			<div onClick$={() => {}}>open</div>
+			<div role="button" tabIndex={0} onClick$={() => {}} onKeyDown$={() => {}}>open</div>
+			<span role="button" onClick$={() => {}}>open</span>
+			<button onClick$={() => {}}>open</button>
+			<a href="/" onClick$={() => {}}>home</a>
+			<div aria-hidden="true" onClick$={() => {}} />
+			<div {...rest} onClick$={() => {}} />
+			{/* @qwik-disable-next-line a11y-img-alt */}
+			<img src="/decoration.svg" />
+		</div>
+	);
+});
+"#;
+	let findings = |transpile_jsx, rules: &[&str]| {
+		let configure = |builder: TransformModulesOptionsBuilder| {
+			rules
+				.iter()
+				.fold(builder.transpile_jsx(transpile_jsx), |builder, code| {
+					builder.rule(*code, RuleSeverity::Warn)
+				})
+		};
+		let mut findings: Vec<_> =
+			lint_diagnostics(code, |code| code.starts_with("a11y-"), configure)
+				.into_iter()
+				.filter_map(|(line, d)| d.code.map(|code| (line, code)))
+				.collect();
+		findings.sort();
+		findings
+	};
+
+	assert_eq!(findings(true, &[]), vec![]);
+	let all = [
+		"a11y-click-events-have-key-events",
+		"a11y-no-static-element-interactions",
+		"a11y-img-alt",
+	];
+	let expected = vec![
+		(5, "a11y-img-alt".to_string()),
+		(7, "a11y-click-events-have-key-events".to_string()),
+		(7, "a11y-no-static-element-interactions".to_string()),
+		(9, "a11y-click-events-have-key-events".to_string()),
+	];
+	assert_eq!(findings(true, &all), expected);
+	assert_eq!(findings(false, &all), expected);
+	assert_eq!(
+		findings(true, &["a11y-img-alt"]),
+		vec![(5, "a11y-img-alt".to_string())]
+	);
+}
@@ -3,9 +3,11 @@ use crate::collector::{
 	collect_from_pat, new_ident_from_id, GlobalCollect, Id, IdentCollector, ImportKind,
 };
 use crate::entry_strategy::EntryPolicy;
+use crate::explain::{CaptureReason, Decision, Explainer, HoistReason, JsxPropReason, PropTarget};
 use crate::inlined_fn::{convert_inlined_fn, render_expr};
 use crate::is_const::is_const_expr;
 use crate::parse::{EmitMode, PathData};
+use crate::utils::SourceLocation;
 
 use crate::words::*;
 use crate::{errors, EntryStrategy};
@@ -22,15 +24,21 @@ use std::str;
 use swc_atoms::{atom, Atom};
 use swc_common::comments::{Comments, SingleThreadedComments};
 use swc_common::SyntaxContext;
-use swc_common::{sync::Lrc, SourceMap, Span, Spanned, DUMMY_SP};
+use swc_common::{sync::Lrc, BytePos, SourceMap, Span, Spanned, DUMMY_SP};
 use swc_ecmascript::ast::{self, SpreadElement};
 use swc_ecmascript::utils::{private_ident, quote_ident, ExprFactory};
 use swc_ecmascript::visit::{noop_fold_type, noop_visit_type, Fold, FoldWith, Visit, VisitWith};
 
-mod disable_next_line_directive;
+mod a11y_lint;
+mod hooks_lint;
+mod jsx_key_lint;
+mod non_serializable_capture_lint;
+mod stale_capture_lint;
+mod suppression_directives;
 #[path = "transform_worker.rs"]
 mod transform_worker;
-use disable_next_line_directive::DisabledDiagnostics;
+use a11y_lint::NativeElement;
+use suppression_directives::{SuggestedFix, Suppressions};
 
 macro_rules! id {
 	($ident: expr) => {
@@ -93,9 +101,14 @@ pub struct SegmentData {
 	pub hash: Atom,
 	pub need_transform: bool,
 	pub migrated_root_vars: Vec<ast::ModuleItem>,
+	/// Every enclosing segment symbol, outermost first. The last entry equals `parent_segment`.
+	pub ancestors: Vec<Atom>,
+	/// Declaration kind of each scoped ident, as found in the enclosing scopes.
+	pub scoped_ident_types: Vec<IdPlusType>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum IdentType {
 	Var(bool),
 	Fn,
@@ -135,6 +148,14 @@ pub struct QwikTransform<'a> {
 	extra_bottom_items: BTreeMap<Id, ast::ModuleItem>,
 	stack_ctxt: Vec<String>,
 	decl_stack: Vec<Vec<IdPlusType>>,
+	/// Spans of function declarations, for the C02 fix that wraps them in `$()`
+	fn_decl_spans: HashMap<Id, Span>,
+	/// Spans of the assignments to each binding, collected by `lint` for `stale-capture`
+	reassignments: HashMap<Id, Vec<Span>>,
+	/// Spans of the loop bodies, a write in the same loop as the QRL is not stale for `stale-capture`
+	loop_bodies: Vec<Span>,
+	/// Bindings passed to a `ref` prop, collected by `lint` for `non-serializable-capture`
+	ref_signals: HashSet<Id>,
 	marker_functions: HashMap<Id, Atom>,
 	jsx_functions: HashSet<Id>,
 	immutable_function_cmp: HashSet<Id>,
@@ -170,9 +191,11 @@ pub struct QwikTransform<'a> {
 	pub ref_assignments: Vec<ast::ModuleItem>,
 	/// Segment idents whose .s() calls are emitted by the Hoist drain, not ref_assignments
 	hoisted_segment_idents: HashSet<Id>,
-	disabled_diagnostics: DisabledDiagnostics,
+	suppressions: Suppressions,
 	/// Pending expression replacement for fold_expr (to return non-CallExpr from fold_call_expr)
 	pending_expr_replacement: Option<ast::Expr>,
+	/// Capture, hoist and JSX prop decisions, recorded when `explain` is enabled
+	pub explain: Explainer,
 }
 
 pub struct QwikTransformOptions<'a> {
@@ -191,7 +214,45 @@ pub struct QwikTransformOptions<'a> {
 	pub strip_ctx_name: Option<&'a [Atom]>,
 	pub strip_event_handlers: bool,
 	pub is_server: bool,
+	pub explain: bool,
 	pub cm: Lrc<SourceMap>,
+	/// Collected with `collect_jsx_attr_spans` before the JSX transform
+	pub jsx_attr_spans: JsxAttrSpans,
+}
+
+/// Spans of the `preventdefault:` JSX attributes, by the start of their element and their name.
+pub type JsxAttrSpans = HashMap<(BytePos, Atom), Span>;
+
+/// The JSX transform turns attributes into props without their span, fixes that remove an
+/// attribute find it here.
+pub fn collect_jsx_attr_spans(program: &ast::Program) -> JsxAttrSpans {
+	let mut collector = JsxAttrSpanCollector::default();
+	program.visit_with(&mut collector);
+	collector.0
+}
+
+#[derive(Default)]
+struct JsxAttrSpanCollector(JsxAttrSpans);
+
+impl Visit for JsxAttrSpanCollector {
+	noop_visit_type!();
+
+	fn visit_jsx_element(&mut self, node: &ast::JSXElement) {
+		for attr in &node.opening.attrs {
+			if let ast::JSXAttrOrSpread::JSXAttr(ast::JSXAttr {
+				name: ast::JSXAttrName::JSXNamespacedName(name),
+				span,
+				..
+			}) = attr
+			{
+				if name.ns.sym == *"preventdefault" {
+					let key = format!("{}:{}", name.ns.sym, name.name.sym);
+					self.0.insert((node.span.lo, key.into()), *span);
+				}
+			}
+		}
+		node.visit_children_with(self);
+	}
 }
 
 fn convert_qrl_word(id: &Atom) -> Option<Atom> {
@@ -288,13 +349,17 @@ impl<'a> QwikTransform<'a> {
 			})
 			.collect();
 
-		let disabled_diagnostics = Self::collect_disabled_diagnostics(&options);
+		let suppressions = Self::collect_suppressions(&options);
 
 		QwikTransform {
 			file_hash: hasher.finish(),
 			jsx_key_counter: 0,
 			stack_ctxt: Vec::with_capacity(16),
 			decl_stack: Vec::with_capacity(32),
+			fn_decl_spans: HashMap::new(),
+			reassignments: HashMap::new(),
+			loop_bodies: Vec::new(),
+			ref_signals: HashSet::new(),
 			segments: Vec::with_capacity(16),
 			segment_stack: Vec::with_capacity(16),
 			extra_top_items: BTreeMap::new(),
@@ -339,8 +404,9 @@ impl<'a> QwikTransform<'a> {
 			const_initializers: HashMap::new(),
 			ref_assignments: Vec::new(),
 			hoisted_segment_idents: HashSet::new(),
-			disabled_diagnostics,
+			suppressions,
 			pending_expr_replacement: None,
+			explain: Explainer::new(options.explain),
 			options,
 		}
 	}
@@ -648,10 +714,12 @@ impl<'a> QwikTransform<'a> {
 					.cloned()
 					.partition(|(_, t)| matches!(t, IdentType::Var(_)));
 				let (scoped, _) = compute_scoped_idents(&descendent_idents, &decl_collect);
+				self.explain_captures(&symbol_name, &descendent_idents, &scoped, &[]);
 				(scoped.clone(), Captures::Auto(scoped))
 			}
 		};
 		let local_idents = self.get_local_idents(&folded);
+		let scoped_ident_types = self.get_ident_types(&scoped_idents);
 		let segment_data = SegmentData {
 			extension: self.options.extension.clone(),
 			local_idents,
@@ -666,6 +734,8 @@ impl<'a> QwikTransform<'a> {
 			need_transform: false,
 			hash,
 			migrated_root_vars: Vec::new(),
+			ancestors: self.segment_stack.clone(),
+			scoped_ident_types,
 		};
 		// Preprocessed inlinedQrl from libs are always emitted — stripping is meant for user code without the user having to write guards; libs can put guards themselves.
 		// App-level $() calls go through _create_synthetic_qsegment which has its own strip check.
@@ -950,6 +1020,12 @@ impl<'a> QwikTransform<'a> {
 				);
 				scoped_idents = vec![];
 			}
+			self.explain_captures(
+				&symbol_name,
+				&descendent_idents,
+				&scoped_idents,
+				&param_idents,
+			);
 
 			// Inject _captures destructuring if there are captured variables
 			let folded = if !scoped_idents.is_empty() {
@@ -960,6 +1036,7 @@ impl<'a> QwikTransform<'a> {
 			};
 
 			let captures = Captures::Auto(scoped_idents.clone());
+			let scoped_ident_types = self.get_ident_types(&scoped_idents);
 			let segment_data = SegmentData {
 				extension: self.options.extension.clone(),
 				local_idents: vec![],
@@ -974,6 +1051,8 @@ impl<'a> QwikTransform<'a> {
 				need_transform: false,
 				hash,
 				migrated_root_vars: Vec::new(),
+				ancestors: self.segment_stack.clone(),
+				scoped_ident_types,
 			};
 
 			return (
@@ -1037,7 +1116,16 @@ impl<'a> QwikTransform<'a> {
 			);
 			scoped_idents = vec![];
 		}
+		self.lint_stale_captures(span, &scoped_idents, &decl_collect);
+		self.lint_non_serializable_captures(span, &scoped_idents);
+		self.explain_captures(
+			&symbol_name,
+			&descendent_idents,
+			&scoped_idents,
+			&param_idents,
+		);
 		let captures = Captures::Auto(scoped_idents.clone());
+		let scoped_ident_types = self.get_ident_types(&scoped_idents);
 		let segment_data = SegmentData {
 			extension: self.options.extension.clone(),
 			local_idents,
@@ -1052,6 +1140,8 @@ impl<'a> QwikTransform<'a> {
 			need_transform: true,
 			hash,
 			migrated_root_vars: Vec::new(),
+			ancestors: self.segment_stack.clone(),
+			scoped_ident_types,
 		};
 		let should_emit = self.should_emit_segment(&segment_data);
 		if should_emit {
@@ -1061,13 +1151,14 @@ impl<'a> QwikTransform<'a> {
 						self.ensure_export(&root_id);
 					}
 					if invalid_decl.iter().any(|entry| entry.0 == *id) {
-						self.emit_error_with_code(
+						self.emit_error_with_fix(
 							first_arg_span,
 							&format!(
 								"Reference to identifier '{}' can not be used inside a Qrl($) scope because it's a function",
 								id.0
 							),
 							errors::get_diagnostic_code(errors::DiagnosticRule::FunctionReference),
+							self.wrap_in_segment_fix(id),
 						);
 					}
 				}
@@ -1106,6 +1197,86 @@ impl<'a> QwikTransform<'a> {
 		}
 	}
 
+	/// Looks up the declaration kind of each ident, innermost scope first.
+	/// Idents that are not declared in an enclosing scope are skipped.
+	fn get_ident_types(&self, idents: &[Id]) -> Vec<IdPlusType> {
+		idents
+			.iter()
+			.filter_map(|ident| {
+				self.decl_stack
+					.iter()
+					.rev()
+					.flat_map(|v| v.iter())
+					.find(|(id, _)| id == ident)
+					.cloned()
+			})
+			.collect()
+	}
+
+	/// Records why each ident referenced by a segment was captured or not.
+	fn explain_captures(
+		&mut self,
+		symbol_name: &Atom,
+		descendent_idents: &[Id],
+		scoped_idents: &[Id],
+		param_idents: &[Id],
+	) {
+		if !self.explain.is_enabled() {
+			return;
+		}
+		for ident in descendent_idents {
+			let (captured, reason) = if scoped_idents.contains(ident) {
+				(true, CaptureReason::DeclaredInScope)
+			} else if param_idents.contains(ident) {
+				(false, CaptureReason::Param)
+			} else if let Some((_, ident_type)) =
+				self.get_ident_types(std::slice::from_ref(ident)).pop()
+			{
+				match ident_type {
+					IdentType::Var(_) => (false, CaptureReason::NotAFunction),
+					IdentType::Fn | IdentType::Class => (false, CaptureReason::FunctionOrClass),
+				}
+			} else if self.options.global_collect.imports.contains_key(ident) {
+				(false, CaptureReason::Import)
+			} else if self.options.global_collect.root.contains_key(ident) {
+				(false, CaptureReason::RootDeclaration)
+			} else {
+				(false, CaptureReason::NotInScope)
+			};
+			self.explain
+				.record(Some(symbol_name), || Decision::Capture {
+					name: ident.0.clone(),
+					captured,
+					reason,
+				});
+		}
+	}
+
+	/// Records whether a JSX prop went to the var or const props of its element.
+	fn explain_jsx_prop(
+		&mut self,
+		name: &Option<Atom>,
+		span: Span,
+		is_const: bool,
+		reason: JsxPropReason,
+	) {
+		let Some(name) = name else {
+			return;
+		};
+		let cm = &self.options.cm;
+		self.explain
+			.record(self.segment_stack.last(), || Decision::JsxProp {
+				name: name.clone(),
+				target: if is_const {
+					PropTarget::Const
+				} else {
+					PropTarget::Var
+				},
+				reason,
+				location: (!span.is_dummy()).then(|| SourceLocation::from(cm, span)),
+			});
+	}
+
 	fn get_local_idents(&self, expr: &ast::Expr) -> Vec<Id> {
 		let mut collector = IdentCollector::new();
 		expr.visit_with(&mut collector);
@@ -1184,6 +1355,11 @@ impl<'a> QwikTransform<'a> {
 			ast::Expr::Object(_) => {}
 			_ => return node,
 		}
+		if let (ast::Expr::Lit(ast::Lit::Str(tag)), ast::Expr::Object(props)) =
+			(&*node.args[0].expr, &*node.args[1].expr)
+		{
+			self.lint_a11y(&NativeElement::from_props(&tag.value, props, node.span));
+		}
 		let node_type = node.args.remove(0);
 		let node_props = node.args.remove(0);
 
@@ -1413,10 +1589,15 @@ impl<'a> QwikTransform<'a> {
 	) -> ast::Expr {
 		let module_hoisted = self.hoist_qrl_to_module_scope(converted_expr, has_moved_captures);
 
+		let segment_name = self.segments.last().map(|s| s.name.clone());
+
 		// If it's just an ident (no captures), no further hoisting needed
 		let with_captures_call = match module_hoisted {
 			ast::Expr::Call(call_expr) => call_expr,
-			expr => return expr,
+			expr => {
+				self.explain_hoist(&segment_name, true, None, HoistReason::NoCaptures);
+				return expr;
+			}
 		};
 
 		// Hoist w calls when inside a loop (for, while, .map(), etc.)
@@ -1425,6 +1606,15 @@ impl<'a> QwikTransform<'a> {
 		let should_hoist =
 			!self.iteration_var_stack.is_empty() && !is_fn && !self.hoisted_qrls.is_empty();
 
+		if !should_hoist {
+			let reason = if is_fn {
+				HoistReason::ComponentQrl
+			} else {
+				HoistReason::NotInLoop
+			};
+			self.explain_hoist(&segment_name, false, None, reason);
+		}
+
 		if should_hoist {
 			// Extract symbol name from the hoisted ident used as the receiver.
 			// The module-scope const is named `q_<symbol>`, so strip the prefix
@@ -1447,6 +1637,21 @@ impl<'a> QwikTransform<'a> {
 
 			let current_depth = self.hoisted_qrls.len() - 1;
 			let target_depth = self.compute_hoist_target_depth(current_depth);
+			let reason = if self
+				.segments
+				.last()
+				.is_some_and(|s| s.data.scoped_idents.is_empty())
+			{
+				HoistReason::ComponentScope
+			} else {
+				HoistReason::OutermostCaptureScope
+			};
+			self.explain_hoist(
+				&segment_name,
+				true,
+				Some((current_depth, target_depth)),
+				reason,
+			);
 
 			// Check if we already hoisted this w call at target depth
 			let existing_var = self.hoisted_qrls[target_depth]
@@ -1493,6 +1698,22 @@ impl<'a> QwikTransform<'a> {
 		}
 	}
 
+	fn explain_hoist(
+		&mut self,
+		segment_name: &Option<Atom>,
+		hoisted: bool,
+		depths: Option<(usize, usize)>,
+		reason: HoistReason,
+	) {
+		self.explain
+			.record(segment_name.as_ref(), || Decision::Hoist {
+				hoisted,
+				current_depth: depths.map(|(current, _)| current),
+				target_depth: depths.map(|(_, target)| target),
+				reason,
+			});
+	}
+
 	fn mark_moved_captures(expr: ast::Expr) -> ast::Expr {
 		ast::Expr::Call(ast::CallExpr {
 			callee: ast::Callee::Expr(Box::new(ast::Expr::Member(ast::MemberExpr {
@@ -1832,7 +2053,7 @@ impl<'a> QwikTransform<'a> {
 				if kw.as_ref().starts_with("preventdefault:") {
 					let event = kw.as_ref().strip_prefix("preventdefault:").unwrap();
 					if context.passive_events.contains(event) {
-						self.emit_span_warning_with_code(
+						self.emit_span_warning_with_fix(
 							node.key.span(),
 							&format!(
 								"preventdefault:{event} has no effect when passive:{event} is also set; passive event listeners cannot call preventDefault()"
@@ -1840,6 +2061,7 @@ impl<'a> QwikTransform<'a> {
 							errors::get_diagnostic_code(
 								errors::DiagnosticRule::PreventdefaultPassiveCheck,
 							),
+							self.remove_jsx_attr_fix(node, kw),
 						);
 						return (key_word, transformed_event_key, true);
 					}
@@ -2492,6 +2714,12 @@ impl<'a> QwikTransform<'a> {
 									}
 								} else if !is_fn && (key_word == *REF || key_word == *QSLOT) {
 									// skip
+									self.explain_jsx_prop(
+										&original_key_word,
+										node.key.span(),
+										false,
+										JsxPropReason::RefOrSlot,
+									);
 									var_props.push(prop.fold_with(self));
 								} else if convert_qrl_word(
 									&original_key_word.clone().unwrap_or_default(),
@@ -2561,6 +2789,18 @@ impl<'a> QwikTransform<'a> {
 											is_fn,
 											has_lifted_captures,
 										));
+										let (in_const, reason) = prop_target(
+											is_const,
+											spread_props_count,
+											JsxPropReason::ConstCaptures,
+											JsxPropReason::MutableCaptures,
+										);
+										self.explain_jsx_prop(
+											&original_key_word,
+											node.key.span(),
+											in_const,
+											reason,
+										);
 										self.add_prop_to_appropriate_list(
 											handler_expr,
 											final_key.clone(),
@@ -2630,6 +2870,18 @@ impl<'a> QwikTransform<'a> {
 										if !const_prop {
 											static_listeners = false;
 										}
+										let (in_const, reason) = prop_target(
+											const_prop,
+											spread_props_count,
+											JsxPropReason::ConstExpression,
+											JsxPropReason::MutableExpression,
+										);
+										self.explain_jsx_prop(
+											&original_key_word,
+											node.key.span(),
+											in_const,
+											reason,
+										);
 
 										self.add_prop_to_appropriate_list(
 											node.value.clone(),
@@ -2649,6 +2901,16 @@ impl<'a> QwikTransform<'a> {
 									&self.options.global_collect,
 									Some(&const_idents),
 								) {
+									self.explain_jsx_prop(
+										&original_key_word,
+										node.key.span(),
+										is_target_const_props,
+										if is_target_const_props {
+											JsxPropReason::ConstExpression
+										} else {
+											JsxPropReason::SpreadProps
+										},
+									);
 									let prop_to_add = self.create_prop_with_transformed_key(
 										node,
 										&prop,
@@ -2680,6 +2942,23 @@ impl<'a> QwikTransform<'a> {
 												value: Box::new(getter),
 											})),
 										);
+										let (in_const, reason) = if captures_iteration_var {
+											(false, JsxPropReason::IterationVariable)
+										} else if is_fn || is_const {
+											if is_target_const_props {
+												(true, JsxPropReason::Getter)
+											} else {
+												(false, JsxPropReason::SpreadProps)
+											}
+										} else {
+											(false, JsxPropReason::MutableExpression)
+										};
+										self.explain_jsx_prop(
+											&original_key_word,
+											node.key.span(),
+											in_const,
+											reason,
+										);
 										// If it captures iteration variables, it must go to var_props
 										if captures_iteration_var {
 											var_props.push(entry);
@@ -2689,6 +2968,12 @@ impl<'a> QwikTransform<'a> {
 											var_props.push(entry);
 										}
 									} else {
+										self.explain_jsx_prop(
+											&original_key_word,
+											node.key.span(),
+											false,
+											JsxPropReason::MutableExpression,
+										);
 										let prop_to_add = self.create_prop_with_transformed_key(
 											node,
 											&prop,
@@ -2710,6 +2995,12 @@ impl<'a> QwikTransform<'a> {
 									Default::default(),
 								))));
 							} else {
+								self.explain_jsx_prop(
+									&Some(key_word),
+									node.span,
+									false,
+									JsxPropReason::Shorthand,
+								);
 								var_props.push(prop.fold_with(self));
 							}
 						}
@@ -3128,6 +3419,65 @@ impl<'a> QwikTransform<'a> {
 		}))
 	}
 
+	/// Runs the lint rules over the program, before it is transformed. Rules that need the
+	/// captures of segments collect what they need here and report during the fold.
+	pub fn lint(&mut self, program: &ast::Program) {
+		self.lint_hooks(program);
+		self.lint_jsx_keys(program);
+		self.collect_reassignments(program);
+		self.collect_ref_signals(program);
+	}
+
+	/// Turns `function foo() {}` into `const foo = $(function foo() {})`, importing `$` if
+	/// needed. Unsafe: the declaration is no longer hoisted and calls to `foo` now get a QRL.
+	fn wrap_in_segment_fix(&self, id: &Id) -> Option<SuggestedFix> {
+		let span = *self.fn_decl_spans.get(id)?;
+		let dollar = self
+			.qsegment_fn
+			.as_ref()
+			.map_or_else(|| QSEGMENT.clone(), |local| local.0.clone());
+		let mut edits = vec![];
+		if self.qsegment_fn.is_none() {
+			let file_start = self.options.cm.lookup_source_file(span.lo).start_pos;
+			edits.push((
+				Span::new(file_start, file_start),
+				format!(
+					"import {{ {} }} from \"{}\";\n",
+					dollar, self.options.core_module
+				),
+			));
+		}
+		edits.push((
+			Span::new(span.lo, span.lo),
+			format!("const {} = {}(", id.0, dollar),
+		));
+		edits.push((Span::new(span.hi, span.hi), ");".to_string()));
+		Some(SuggestedFix {
+			message: format!("Wrap {} in $()", id.0),
+			edits,
+			safe: false,
+		})
+	}
+
+	/// Removes the `name` attribute that became `prop`, with the whitespace before it.
+	fn remove_jsx_attr_fix(&self, prop: &ast::KeyValueProp, name: &str) -> Option<SuggestedFix> {
+		// The JSX transform gives the key the span of the element
+		let attr = self
+			.options
+			.jsx_attr_spans
+			.get(&(prop.key.span().lo, Atom::from(name)))?;
+		let before = self.options.cm.span_to_prev_source(*attr).ok()?;
+		let space = before.len() - before.trim_end().len();
+		Some(SuggestedFix {
+			message: format!("Remove {}", name),
+			edits: vec![(
+				Span::new(attr.lo - BytePos(space as u32), attr.hi),
+				String::new(),
+			)],
+			safe: true,
+		})
+	}
+
 	fn should_emit_segment(&self, segment_data: &SegmentData) -> bool {
 		if let Some(strip_ctx_name) = self.options.strip_ctx_name {
 			if strip_ctx_name
@@ -3652,6 +4002,8 @@ impl<'a> Fold for QwikTransform<'a> {
 		if let Some(current_scope) = self.decl_stack.last_mut() {
 			current_scope.push((id!(node.ident), IdentType::Fn));
 		}
+		self.fn_decl_spans
+			.insert(id!(node.ident), node.function.span);
 		self.stack_ctxt.push(node.ident.sym.to_string());
 
 		let o = node.fold_children_with(self);
@@ -3977,6 +4329,9 @@ impl<'a> Fold for QwikTransform<'a> {
 		{
 			// Native elements start with lowercase, components with uppercase
 			let is_native_element = ident.sym.chars().next().is_some_and(|c| c.is_lowercase());
+			if is_native_element {
+				self.lint_a11y(&NativeElement::from_jsx(&ident.sym, &node.opening));
+			}
 			self.stack_ctxt.push(ident.sym.to_string());
 			self.jsx_element_is_native.push(is_native_element);
 			self.jsx_element_passive_events
@@ -4130,21 +4485,7 @@ impl<'a> Fold for QwikTransform<'a> {
 		let mut ctx_name: Atom = QSEGMENT.clone();
 
 		// Check if this is an array iteration method call (e.g., .map(), .filter(), etc.)
-		let is_iteration_method =
-			if let ast::Callee::Expr(box ast::Expr::Member(member)) = &node.callee {
-				matches!(
-					prop_to_string(&member.prop).as_deref(),
-					Some(
-						"map"
-							| "filter" | "forEach"
-							| "flatMap" | "some" | "every"
-							| "find" | "findIndex"
-							| "reduce" | "reduceRight"
-					)
-				)
-			} else {
-				false
-			};
+		let is_iteration_method = iteration_method(&node.callee).is_some();
 
 		// Track iteration variable for array methods
 		if is_iteration_method {
@@ -5172,6 +5513,22 @@ fn transform_event_handler_with_iter_var(
 	}
 }
 
+/// Which props list `add_prop_to_appropriate_list` puts a prop in, and why.
+const fn prop_target(
+	is_const: bool,
+	spread_props_count: usize,
+	const_reason: JsxPropReason,
+	var_reason: JsxPropReason,
+) -> (bool, JsxPropReason) {
+	if spread_props_count > 0 {
+		(false, JsxPropReason::SpreadProps)
+	} else if is_const {
+		(true, const_reason)
+	} else {
+		(false, var_reason)
+	}
+}
+
 fn compute_scoped_idents(all_idents: &[Id], all_decl: &[IdPlusType]) -> (Vec<Id>, bool) {
 	let mut set: HashSet<Id> = HashSet::new();
 	let mut is_const = true;
@@ -5248,6 +5605,25 @@ fn get_qrl_dev_obj(abs_path: Atom, segment: &SegmentData, span: &Span) -> ast::E
 	})
 }
 
+/// The name of the array method called by `callee`, when its callback runs once per item,
+/// e.g. `.map()` or `.filter()`.
+fn iteration_method(callee: &ast::Callee) -> Option<Atom> {
+	let ast::Callee::Expr(box ast::Expr::Member(member)) = callee else {
+		return None;
+	};
+	prop_to_string(&member.prop).filter(|name| {
+		matches!(
+			name.as_ref(),
+			"map"
+				| "filter" | "forEach"
+				| "flatMap" | "some"
+				| "every" | "find"
+				| "findIndex"
+				| "reduce" | "reduceRight"
+		)
+	})
+}
+
 fn prop_to_string(prop: &ast::MemberProp) -> Option<Atom> {
 	match prop {
 		ast::MemberProp::Ident(ident) => Some(ident.sym.clone()),
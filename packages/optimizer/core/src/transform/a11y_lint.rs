@@ -0,0 +1,174 @@
+use swc_common::{Span, Spanned};
+use swc_ecmascript::ast;
+
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::QwikTransform;
+
+/// Elements the browser lets users focus and activate with the keyboard. `<a>` and `<area>`
+/// only are with an `href`.
+const INTERACTIVE_ELEMENTS: &[&str] =
+	&["button", "input", "option", "select", "summary", "textarea"];
+
+const KEYBOARD_HANDLERS: &[&str] = &["onkeydown$", "onkeyup$", "onkeypress$"];
+
+enum PropValue<'a> {
+	Str(&'a str),
+	Expr(&'a ast::Expr),
+	/// An attribute without a value, e.g. `<input disabled />`
+	Flag,
+}
+
+/// The tag and props of a native element, in JSX or in a `jsx()` call.
+pub(super) struct NativeElement<'a> {
+	tag: &'a str,
+	span: Span,
+	props: Vec<(&'a str, PropValue<'a>, Span)>,
+	/// Props may come from a spread, so missing ones are unknown
+	has_spread: bool,
+}
+
+impl<'a> NativeElement<'a> {
+	pub(super) fn from_jsx(tag: &'a str, opening: &'a ast::JSXOpeningElement) -> Self {
+		let mut has_spread = false;
+		let mut props = vec![];
+		for attr in &opening.attrs {
+			match attr {
+				ast::JSXAttrOrSpread::SpreadElement(_) => has_spread = true,
+				ast::JSXAttrOrSpread::JSXAttr(ast::JSXAttr {
+					name: ast::JSXAttrName::Ident(name),
+					value,
+					span,
+				}) => {
+					let value = match value {
+						None => PropValue::Flag,
+						Some(ast::JSXAttrValue::Lit(ast::Lit::Str(str))) => {
+							PropValue::Str(&str.value)
+						}
+						Some(ast::JSXAttrValue::JSXExprContainer(ast::JSXExprContainer {
+							expr: ast::JSXExpr::Expr(expr),
+							..
+						})) => PropValue::Expr(expr),
+						Some(_) => continue,
+					};
+					props.push((name.sym.as_ref(), value, *span));
+				}
+				ast::JSXAttrOrSpread::JSXAttr(_) => {}
+			}
+		}
+		Self {
+			tag,
+			span: opening.span,
+			props,
+			has_spread,
+		}
+	}
+
+	/// `jsx("div", { ... })` with a props object literal, `span` is the call.
+	pub(super) fn from_props(tag: &'a str, obj: &'a ast::ObjectLit, span: Span) -> Self {
+		let mut has_spread = false;
+		let mut props = vec![];
+		for prop in &obj.props {
+			match prop {
+				ast::PropOrSpread::Spread(_) => has_spread = true,
+				ast::PropOrSpread::Prop(box ast::Prop::KeyValue(prop)) => {
+					let name = match &prop.key {
+						ast::PropName::Ident(ident) => ident.sym.as_ref(),
+						ast::PropName::Str(str) => str.value.as_ref(),
+						_ => continue,
+					};
+					let value = match &*prop.value {
+						ast::Expr::Lit(ast::Lit::Str(str)) => PropValue::Str(&str.value),
+						expr => PropValue::Expr(expr),
+					};
+					props.push((name, value, prop.key.span()));
+				}
+				ast::PropOrSpread::Prop(box ast::Prop::Shorthand(ident)) => {
+					props.push((ident.sym.as_ref(), PropValue::Flag, ident.span));
+				}
+				ast::PropOrSpread::Prop(_) => {}
+			}
+		}
+		Self {
+			tag,
+			span,
+			props,
+			has_spread,
+		}
+	}
+
+	fn prop(&self, name: &str) -> Option<&(&'a str, PropValue<'a>, Span)> {
+		self.props
+			.iter()
+			.find(|(prop, _, _)| prop.eq_ignore_ascii_case(name))
+	}
+
+	fn is_interactive(&self) -> bool {
+		INTERACTIVE_ELEMENTS.contains(&self.tag)
+			|| (matches!(self.tag, "a" | "area") && self.prop("href").is_some())
+	}
+
+	/// `aria-hidden` elements are not exposed to assistive technologies.
+	fn is_hidden(&self) -> bool {
+		matches!(
+			self.prop("aria-hidden"),
+			Some((_, PropValue::Flag | PropValue::Str("true"), _))
+				| Some((
+					_,
+					PropValue::Expr(ast::Expr::Lit(ast::Lit::Bool(ast::Bool {
+						value: true,
+						..
+					}))),
+					_
+				))
+		)
+	}
+}
+
+impl QwikTransform<'_> {
+	/// Accessibility checks of a native element: click handlers that can't be reached with the
+	/// keyboard, and images without alternative text. Their rules are off unless enabled with
+	/// the `rules` option.
+	pub(super) fn lint_a11y(&self, element: &NativeElement) {
+		if element.tag == "img" && element.prop("alt").is_none() && !element.has_spread {
+			self.emit_span_warning_with_code(
+				element.span,
+				"<img> has no alt text, describe the image in alt, or use alt=\"\" when it is decorative",
+				get_diagnostic_code(DiagnosticRule::A11yImgAlt),
+			);
+		}
+
+		let Some((name, _, span)) = element.prop("onClick$") else {
+			return;
+		};
+		if element.is_interactive() || element.is_hidden() {
+			return;
+		}
+		let has_keyboard_handler = element
+			.props
+			.iter()
+			.any(|(prop, _, _)| KEYBOARD_HANDLERS.contains(&&*prop.to_ascii_lowercase()));
+		if !has_keyboard_handler && !element.has_spread {
+			self.emit_span_warning_with_code(
+				*span,
+				&format!(
+					"{} on <{}> has no keyboard equivalent, add onKeyDown$ or onKeyUp$ so keyboard users can trigger it too",
+					name, element.tag
+				),
+				get_diagnostic_code(DiagnosticRule::A11yClickEventsHaveKeyEvents),
+			);
+		}
+		if element.prop("role").is_none()
+			&& element.prop("tabIndex").is_none()
+			&& !element.has_spread
+		{
+			self.emit_span_warning_with_code(
+				*span,
+				&format!(
+					"{} on <{}> without a role or tabindex, assistive technologies and keyboard navigation don't know it is interactive. Use a <button>, or add a role and tabindex",
+					name, element.tag
+				),
+				get_diagnostic_code(DiagnosticRule::A11yNoStaticElementInteractions),
+			);
+		}
+	}
+}
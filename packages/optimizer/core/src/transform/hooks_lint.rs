@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+
+use swc_atoms::Atom;
+use swc_common::Span;
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{Visit, VisitWith};
+
+use crate::collector::Id;
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::QwikTransform;
+use crate::words::{QCOMPONENT, QRL_SUFFIX, _INLINED_QRL, _INLINED_QRL_DEV};
+
+lazy_static::lazy_static! {
+	static ref QCOMPONENT_QRL: Atom = Atom::from("componentQrl");
+}
+
+/// What kind of function a hook call is directly in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HookScope {
+	Component,
+	/// A function named `use*`, which may call other hooks
+	CustomHook,
+	/// The callback of a `$`-suffixed call, e.g. `useTask$` or `onClick$`
+	Qrl,
+	Function,
+}
+
+struct Frame {
+	scope: HookScope,
+	conditionals: u32,
+	loops: u32,
+}
+
+/// Finds `use*` calls that may not run in the same order on every render of a component.
+struct HooksLint<'a> {
+	components: HashSet<Id>,
+	/// `inlinedQrl(fn, ...)` in pre-built code, `fn` keeps the scope of the call
+	wrappers: HashSet<Id>,
+	marker_functions: &'a HashMap<Id, Atom>,
+	frames: Vec<Frame>,
+	/// Scope for the function about to be visited, set for the callee's first argument
+	pending: Option<HookScope>,
+	findings: Vec<(Span, String)>,
+}
+
+impl HooksLint<'_> {
+	/// Plain functions calling hooks outside of a component may be hooks themselves, e.g. those
+	/// built by `routeAction$`, so they are only reported when nested in a component or hook.
+	fn check_hook(&mut self, span: Span, name: &Atom) {
+		let problem = match self.frames.last() {
+			None => "outside of a component$",
+			Some(frame) => match frame.scope {
+				HookScope::Qrl => "inside a QRL callback",
+				HookScope::Function
+					if self.frames.iter().any(|frame| {
+						matches!(frame.scope, HookScope::Component | HookScope::CustomHook)
+					}) =>
+				{
+					"inside a nested function"
+				}
+				HookScope::Function => return,
+				_ if frame.loops > 0 => "in a loop",
+				_ if frame.conditionals > 0 => "conditionally",
+				_ => return,
+			},
+		};
+		self.findings.push((
+			span,
+			format!(
+				"{} is called {}, hooks must be called at the top level of a component$ or of a use* function so they run in the same order on every render",
+				name, problem
+			),
+		));
+	}
+
+	fn in_function(&mut self, visit: impl FnOnce(&mut Self)) {
+		let scope = self.pending.take().unwrap_or(HookScope::Function);
+		self.frames.push(Frame {
+			scope,
+			conditionals: 0,
+			loops: 0,
+		});
+		visit(self);
+		self.frames.pop();
+	}
+
+	fn conditionally(&mut self, node: &impl VisitWith<Self>) {
+		self.pending = None;
+		if let Some(frame) = self.frames.last_mut() {
+			frame.conditionals += 1;
+		}
+		node.visit_with(self);
+		if let Some(frame) = self.frames.last_mut() {
+			frame.conditionals -= 1;
+		}
+	}
+
+	fn in_loop(&mut self, node: &impl VisitWith<Self>) {
+		self.pending = None;
+		if let Some(frame) = self.frames.last_mut() {
+			frame.loops += 1;
+		}
+		node.visit_with(self);
+		if let Some(frame) = self.frames.last_mut() {
+			frame.loops -= 1;
+		}
+	}
+}
+
+/// `useLexicalScope` reads the captures of a segment, it is called by the optimizer's output
+/// at the top of QRL callbacks.
+fn is_hook_name(name: &str) -> bool {
+	name != "useLexicalScope"
+		&& name
+			.strip_prefix("use")
+			.and_then(|rest| rest.chars().next())
+			.is_some_and(|c| c.is_ascii_uppercase())
+}
+
+impl Visit for HooksLint<'_> {
+	fn visit_call_expr(&mut self, call: &ast::CallExpr) {
+		let inherited = self.pending.take();
+		let callee = match &call.callee {
+			ast::Callee::Expr(box ast::Expr::Ident(ident)) => Some(ident.to_id()),
+			_ => None,
+		};
+		if let Some(callee) = &callee {
+			if is_hook_name(&callee.0) {
+				self.check_hook(call.span, &callee.0);
+			}
+		}
+		call.callee.visit_with(self);
+		for (i, arg) in call.args.iter().enumerate() {
+			self.pending = match &callee {
+				Some(callee) if i == 0 => {
+					if self.components.contains(callee) {
+						Some(HookScope::Component)
+					} else if self.wrappers.contains(callee) {
+						inherited
+					} else if self.marker_functions.contains_key(callee) {
+						Some(HookScope::Qrl)
+					} else {
+						None
+					}
+				}
+				_ => None,
+			};
+			arg.visit_with(self);
+		}
+		self.pending = None;
+	}
+
+	fn visit_function(&mut self, function: &ast::Function) {
+		self.in_function(|lint| function.visit_children_with(lint));
+	}
+
+	fn visit_arrow_expr(&mut self, arrow: &ast::ArrowExpr) {
+		self.in_function(|lint| arrow.visit_children_with(lint));
+	}
+
+	fn visit_fn_decl(&mut self, decl: &ast::FnDecl) {
+		self.pending = is_hook_name(&decl.ident.sym).then_some(HookScope::CustomHook);
+		decl.function.visit_with(self);
+	}
+
+	fn visit_var_declarator(&mut self, decl: &ast::VarDeclarator) {
+		decl.name.visit_with(self);
+		self.pending = match &decl.name {
+			ast::Pat::Ident(ident) if is_hook_name(&ident.sym) => Some(HookScope::CustomHook),
+			_ => None,
+		};
+		decl.init.visit_with(self);
+		self.pending = None;
+	}
+
+	/// `onClick$={() => ...}`, after and before JSX is transpiled
+	fn visit_key_value_prop(&mut self, prop: &ast::KeyValueProp) {
+		prop.key.visit_with(self);
+		self.pending = match &prop.key {
+			ast::PropName::Ident(ast::IdentName { sym, .. })
+			| ast::PropName::Str(ast::Str { value: sym, .. })
+				if sym.ends_with(QRL_SUFFIX) =>
+			{
+				Some(HookScope::Qrl)
+			}
+			_ => None,
+		};
+		prop.value.visit_with(self);
+		self.pending = None;
+	}
+
+	fn visit_jsx_attr(&mut self, attr: &ast::JSXAttr) {
+		self.pending = match &attr.name {
+			ast::JSXAttrName::Ident(ident) if ident.sym.ends_with(QRL_SUFFIX) => {
+				Some(HookScope::Qrl)
+			}
+			_ => None,
+		};
+		attr.value.visit_with(self);
+		self.pending = None;
+	}
+
+	fn visit_if_stmt(&mut self, stmt: &ast::IfStmt) {
+		stmt.test.visit_with(self);
+		self.conditionally(&stmt.cons);
+		self.conditionally(&stmt.alt);
+	}
+
+	fn visit_cond_expr(&mut self, expr: &ast::CondExpr) {
+		expr.test.visit_with(self);
+		self.conditionally(&expr.cons);
+		self.conditionally(&expr.alt);
+	}
+
+	fn visit_bin_expr(&mut self, expr: &ast::BinExpr) {
+		expr.left.visit_with(self);
+		if matches!(
+			expr.op,
+			ast::BinaryOp::LogicalAnd | ast::BinaryOp::LogicalOr | ast::BinaryOp::NullishCoalescing
+		) {
+			self.conditionally(&expr.right);
+		} else {
+			expr.right.visit_with(self);
+		}
+	}
+
+	fn visit_switch_stmt(&mut self, stmt: &ast::SwitchStmt) {
+		stmt.discriminant.visit_with(self);
+		self.conditionally(&stmt.cases);
+	}
+
+	fn visit_for_stmt(&mut self, stmt: &ast::ForStmt) {
+		stmt.init.visit_with(self);
+		self.in_loop(&stmt.test);
+		self.in_loop(&stmt.update);
+		self.in_loop(&stmt.body);
+	}
+
+	fn visit_for_in_stmt(&mut self, stmt: &ast::ForInStmt) {
+		stmt.right.visit_with(self);
+		self.in_loop(&stmt.body);
+	}
+
+	fn visit_for_of_stmt(&mut self, stmt: &ast::ForOfStmt) {
+		stmt.right.visit_with(self);
+		self.in_loop(&stmt.body);
+	}
+
+	fn visit_while_stmt(&mut self, stmt: &ast::WhileStmt) {
+		self.in_loop(&stmt.test);
+		self.in_loop(&stmt.body);
+	}
+
+	fn visit_do_while_stmt(&mut self, stmt: &ast::DoWhileStmt) {
+		self.in_loop(&stmt.body);
+		self.in_loop(&stmt.test);
+	}
+}
+
+impl QwikTransform<'_> {
+	/// Warns about hook calls in conditionals, loops, nested functions and QRL callbacks, and
+	/// outside of `component$`.
+	pub(super) fn lint_hooks(&self, program: &ast::Program) {
+		let imported = |name: &Atom| {
+			self.options
+				.global_collect
+				.get_imported_local(name, &self.options.core_module)
+		};
+		let mut lint = HooksLint {
+			components: [&*QCOMPONENT, &*QCOMPONENT_QRL]
+				.into_iter()
+				.filter_map(imported)
+				.collect(),
+			wrappers: [&*_INLINED_QRL, &*_INLINED_QRL_DEV]
+				.into_iter()
+				.filter_map(imported)
+				.collect(),
+			marker_functions: &self.marker_functions,
+			frames: vec![],
+			pending: None,
+			findings: vec![],
+		};
+		program.visit_with(&mut lint);
+		for (span, message) in lint.findings {
+			self.emit_span_warning_with_code(
+				span,
+				&message,
+				get_diagnostic_code(DiagnosticRule::RulesOfHooks),
+			);
+		}
+	}
+}
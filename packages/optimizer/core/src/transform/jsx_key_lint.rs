@@ -0,0 +1,234 @@
+use std::collections::HashSet;
+
+use swc_atoms::Atom;
+use swc_common::{Span, Spanned};
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{Visit, VisitWith};
+
+use crate::collector::Id;
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::{iteration_method, QwikTransform};
+
+/// Finds JSX returned from iteration callbacks without a key, or keyed by the item index.
+struct JsxKeyLint<'a> {
+	jsx_functions: &'a HashSet<Id>,
+	findings: Vec<(Span, String)>,
+}
+
+/// The values a callback body may return, without looking into nested functions.
+#[derive(Default)]
+struct Returns<'a>(Vec<&'a ast::Expr>);
+
+impl<'a> Returns<'a> {
+	fn visit_stmts(&mut self, stmts: &'a [ast::Stmt]) {
+		for stmt in stmts {
+			self.visit_stmt(stmt);
+		}
+	}
+
+	fn visit_stmt(&mut self, stmt: &'a ast::Stmt) {
+		match stmt {
+			ast::Stmt::Return(ast::ReturnStmt { arg: Some(arg), .. }) => self.0.push(arg),
+			ast::Stmt::Block(block) => self.visit_stmts(&block.stmts),
+			ast::Stmt::If(stmt) => {
+				self.visit_stmt(&stmt.cons);
+				if let Some(alt) = &stmt.alt {
+					self.visit_stmt(alt);
+				}
+			}
+			ast::Stmt::Switch(stmt) => {
+				for case in &stmt.cases {
+					self.visit_stmts(&case.cons);
+				}
+			}
+			ast::Stmt::Try(stmt) => {
+				self.visit_stmts(&stmt.block.stmts);
+				if let Some(handler) = &stmt.handler {
+					self.visit_stmts(&handler.body.stmts);
+				}
+			}
+			ast::Stmt::Labeled(stmt) => self.visit_stmt(&stmt.body),
+			_ => {}
+		}
+	}
+}
+
+impl JsxKeyLint<'_> {
+	fn check_callback(&mut self, method: &Atom, callback: &ast::Expr) {
+		let (params, returns): (Vec<&ast::Pat>, Returns) = match callback {
+			ast::Expr::Arrow(arrow) => {
+				let mut returns = Returns::default();
+				match &*arrow.body {
+					ast::BlockStmtOrExpr::Expr(expr) => returns.0.push(expr),
+					ast::BlockStmtOrExpr::BlockStmt(block) => returns.visit_stmts(&block.stmts),
+				}
+				(arrow.params.iter().collect(), returns)
+			}
+			ast::Expr::Fn(ast::FnExpr { function, .. }) => {
+				let mut returns = Returns::default();
+				if let Some(body) = &function.body {
+					returns.visit_stmts(&body.stmts);
+				}
+				(
+					function.params.iter().map(|param| &param.pat).collect(),
+					returns,
+				)
+			}
+			_ => return,
+		};
+		let index = match params.get(1) {
+			Some(ast::Pat::Ident(ident)) => Some(ident.to_id()),
+			_ => None,
+		};
+		for expr in returns.0 {
+			self.check_returned(method, expr, index.as_ref());
+		}
+	}
+
+	fn check_returned(&mut self, method: &Atom, expr: &ast::Expr, index: Option<&Id>) {
+		let (span, key) = match expr {
+			ast::Expr::Paren(paren) => return self.check_returned(method, &paren.expr, index),
+			ast::Expr::Cond(cond) => {
+				self.check_returned(method, &cond.cons, index);
+				return self.check_returned(method, &cond.alt, index);
+			}
+			ast::Expr::Bin(bin) => {
+				if matches!(
+					bin.op,
+					ast::BinaryOp::LogicalOr | ast::BinaryOp::NullishCoalescing
+				) {
+					self.check_returned(method, &bin.left, index);
+				}
+				if matches!(
+					bin.op,
+					ast::BinaryOp::LogicalAnd
+						| ast::BinaryOp::LogicalOr
+						| ast::BinaryOp::NullishCoalescing
+				) {
+					self.check_returned(method, &bin.right, index);
+				}
+				return;
+			}
+			ast::Expr::Call(call) => match &call.callee {
+				ast::Callee::Expr(box ast::Expr::Ident(ident))
+					if self.jsx_functions.contains(&ident.to_id()) =>
+				{
+					let spread = matches!(
+						call.args.get(1).map(|arg| &*arg.expr),
+						Some(ast::Expr::Object(obj))
+							if obj.props.iter().any(|prop| matches!(prop, ast::PropOrSpread::Spread(_)))
+					);
+					match call.args.get(2) {
+						Some(key) => (call.span, Some(&*key.expr)),
+						// the key may be spread in
+						None if spread => return,
+						None => (call.span, None),
+					}
+				}
+				_ => return,
+			},
+			ast::Expr::JSXElement(element) => {
+				let mut spread = false;
+				let mut key = None;
+				for attr in &element.opening.attrs {
+					match attr {
+						ast::JSXAttrOrSpread::SpreadElement(_) => spread = true,
+						ast::JSXAttrOrSpread::JSXAttr(ast::JSXAttr {
+							name: ast::JSXAttrName::Ident(name),
+							value,
+							..
+						}) if name.sym == "key" => {
+							key = Some(match value {
+								Some(ast::JSXAttrValue::JSXExprContainer(
+									ast::JSXExprContainer {
+										expr: ast::JSXExpr::Expr(expr),
+										..
+									},
+								)) => Some(&**expr),
+								_ => None,
+							});
+						}
+						_ => {}
+					}
+				}
+				match key {
+					Some(Some(key)) => (element.opening.span, Some(key)),
+					Some(None) => return,
+					None if spread => return,
+					None => (element.opening.span, None),
+				}
+			}
+			ast::Expr::JSXFragment(fragment) => (fragment.opening.span, None),
+			_ => return,
+		};
+
+		match key {
+			None => self.findings.push((
+				span,
+				format!(
+					"JSX returned from a .{}() callback has no key, list items without a key can be matched with the wrong DOM nodes when the list changes",
+					method
+				),
+			)),
+			Some(key) => {
+				if let Some(index) = index.filter(|index| is_index(key, index)) {
+					self.findings.push((
+						key.span(),
+						format!(
+							"Index parameter {} of the .{}() callback is used as key, it should identify the item so it stays the same when the list is reordered",
+							index.0, method
+						),
+					));
+				}
+			}
+		}
+	}
+}
+
+/// `index` or `${index}`
+fn is_index(key: &ast::Expr, index: &Id) -> bool {
+	match key {
+		ast::Expr::Paren(paren) => is_index(&paren.expr, index),
+		ast::Expr::Ident(ident) => ident.to_id() == *index,
+		ast::Expr::Tpl(tpl) => {
+			tpl.exprs.len() == 1
+				&& tpl.quasis.iter().all(|quasi| quasi.raw.is_empty())
+				&& is_index(&tpl.exprs[0], index)
+		}
+		_ => false,
+	}
+}
+
+impl Visit for JsxKeyLint<'_> {
+	fn visit_call_expr(&mut self, call: &ast::CallExpr) {
+		call.visit_children_with(self);
+		// the callbacks of reduce return the accumulator, not an item
+		let Some(method) = iteration_method(&call.callee)
+			.filter(|method| !matches!(method.as_ref(), "reduce" | "reduceRight"))
+		else {
+			return;
+		};
+		if let Some(callback) = call.args.first() {
+			self.check_callback(&method, &callback.expr);
+		}
+	}
+}
+
+impl QwikTransform<'_> {
+	/// Warns about JSX returned from `.map()` and other iteration callbacks that has no `key`
+	/// or uses the index parameter as its key.
+	pub(super) fn lint_jsx_keys(&self, program: &ast::Program) {
+		let mut lint = JsxKeyLint {
+			jsx_functions: &self.jsx_functions,
+			findings: vec![],
+		};
+		program.visit_with(&mut lint);
+		for (span, message) in lint.findings {
+			self.emit_span_warning_with_code(
+				span,
+				&message,
+				get_diagnostic_code(DiagnosticRule::JsxKey),
+			);
+		}
+	}
+}
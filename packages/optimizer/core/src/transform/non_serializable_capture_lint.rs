@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use swc_common::Span;
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{Visit, VisitWith};
+
+use crate::collector::Id;
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::QwikTransform;
+
+/// Classes whose instances the Qwik serializer knows how to write.
+const SERIALIZABLE_CLASSES: &[&str] = &[
+	"Array",
+	"Date",
+	"Error",
+	"FormData",
+	"Map",
+	"Object",
+	"RegExp",
+	"Set",
+	"Uint8Array",
+	"URL",
+	"URLSearchParams",
+];
+
+/// `document` methods and properties that return DOM nodes.
+const DOCUMENT_NODES: &[&str] = &[
+	"activeElement",
+	"body",
+	"createElement",
+	"createTextNode",
+	"documentElement",
+	"getElementById",
+	"getElementsByClassName",
+	"getElementsByTagName",
+	"head",
+	"querySelector",
+	"querySelectorAll",
+];
+
+/// Bindings passed to a `ref` prop, their `.value` is a DOM node.
+#[derive(Default)]
+struct RefSignals(HashSet<Id>);
+
+impl Visit for RefSignals {
+	fn visit_jsx_attr(&mut self, attr: &ast::JSXAttr) {
+		if let (
+			ast::JSXAttrName::Ident(name),
+			Some(ast::JSXAttrValue::JSXExprContainer(ast::JSXExprContainer {
+				expr: ast::JSXExpr::Expr(box ast::Expr::Ident(ident)),
+				..
+			})),
+		) = (&attr.name, &attr.value)
+		{
+			if name.sym == "ref" {
+				self.0.insert(ident.to_id());
+			}
+		}
+		attr.visit_children_with(self);
+	}
+
+	/// `ref` in the props of transpiled JSX
+	fn visit_key_value_prop(&mut self, prop: &ast::KeyValueProp) {
+		if let (ast::PropName::Ident(name), ast::Expr::Ident(ident)) = (&prop.key, &*prop.value) {
+			if name.sym == "ref" {
+				self.0.insert(ident.to_id());
+			}
+		}
+		prop.visit_children_with(self);
+	}
+}
+
+const fn is_function(expr: &ast::Expr) -> bool {
+	matches!(expr, ast::Expr::Arrow(_) | ast::Expr::Fn(_))
+}
+
+/// `new Map([[key, () => {}]])` or `new Set([() => {}])`
+fn has_function_entries(new: &ast::NewExpr, class: &str) -> bool {
+	let Some(box ast::Expr::Array(entries)) = new
+		.args
+		.as_ref()
+		.and_then(|args| args.first())
+		.map(|arg| &arg.expr)
+	else {
+		return false;
+	};
+	entries
+		.elems
+		.iter()
+		.flatten()
+		.any(|entry| match (class, &*entry.expr) {
+			("Map", ast::Expr::Array(pair)) => pair
+				.elems
+				.get(1)
+				.and_then(|value| value.as_ref())
+				.is_some_and(|value| is_function(&value.expr)),
+			("Set", value) => is_function(value),
+			_ => false,
+		})
+}
+
+impl QwikTransform<'_> {
+	pub(super) fn collect_ref_signals(&mut self, program: &ast::Program) {
+		let mut refs = RefSignals::default();
+		program.visit_with(&mut refs);
+		self.ref_signals = refs.0;
+	}
+
+	/// The global `name`, e.g. `document`, not a binding of the module.
+	fn is_unbound(&self, expr: &ast::Expr, name: &str) -> bool {
+		match expr {
+			ast::Expr::Ident(ident) => {
+				let id = ident.to_id();
+				ident.sym == name
+					&& !self.options.global_collect.is_global(&id)
+					&& !self
+						.decl_stack
+						.iter()
+						.flatten()
+						.any(|(decl, _)| *decl == id)
+			}
+			_ => false,
+		}
+	}
+
+	/// What the const initializer `init` creates, when it is known not to be serializable. Plain
+	/// aliases such as `const doc = document` are not kept in `const_initializers`.
+	fn non_serializable_value(&self, init: &ast::Expr) -> Option<String> {
+		match init {
+			ast::Expr::Paren(paren) => self.non_serializable_value(&paren.expr),
+			ast::Expr::Arrow(_) | ast::Expr::Fn(_) => Some("a function".into()),
+			ast::Expr::Member(ast::MemberExpr {
+				obj,
+				prop: ast::MemberProp::Ident(prop),
+				..
+			}) => {
+				if self.is_unbound(obj, "document") && DOCUMENT_NODES.contains(&&*prop.sym) {
+					Some(format!("a DOM node (document.{})", prop.sym))
+				} else if prop.sym == "value"
+					&& matches!(&**obj, ast::Expr::Ident(ident) if self.ref_signals.contains(&ident.to_id()))
+				{
+					Some("a DOM node from a ref".into())
+				} else {
+					None
+				}
+			}
+			ast::Expr::Call(ast::CallExpr {
+				callee: ast::Callee::Expr(callee),
+				..
+			}) => match &**callee {
+				ast::Expr::Member(ast::MemberExpr {
+					obj,
+					prop: ast::MemberProp::Ident(prop),
+					..
+				}) => {
+					if self.is_unbound(obj, "document") && DOCUMENT_NODES.contains(&&*prop.sym) {
+						Some(format!("a DOM node (document.{}())", prop.sym))
+					} else if self.is_unbound(obj, "Promise") {
+						Some(format!("a Promise (Promise.{}())", prop.sym))
+					} else {
+						None
+					}
+				}
+				fetch if self.is_unbound(fetch, "fetch") => Some("a Promise (fetch())".into()),
+				_ => None,
+			},
+			ast::Expr::New(new) => {
+				let ast::Expr::Ident(class) = &*new.callee else {
+					return Some("a class instance".into());
+				};
+				match &*class.sym {
+					"Promise" => Some("a Promise".into()),
+					"Map" | "Set" if has_function_entries(new, &class.sym) => {
+						Some(format!("a {} with function values", class.sym))
+					}
+					name if SERIALIZABLE_CLASSES.contains(&name) => None,
+					name => Some(format!("a class instance (new {}())", name)),
+				}
+			}
+			_ => None,
+		}
+	}
+
+	/// Warns when the segment at `span` captures a const whose initializer creates a value the
+	/// serializer can't write, it would otherwise only fail when the QRL is serialized.
+	pub(super) fn lint_non_serializable_captures(&self, span: Span, scoped_idents: &[Id]) {
+		for id in scoped_idents {
+			let Some(what) = self
+				.const_initializers
+				.get(id)
+				.and_then(|init| self.non_serializable_value(init))
+			else {
+				continue;
+			};
+			self.emit_span_warning_with_code(
+				span,
+				&format!(
+					"{} is captured by this QRL but holds {}, which can not be serialized. Create it inside the QRL, or wrap it in noSerialize()",
+					id.0, what
+				),
+				get_diagnostic_code(DiagnosticRule::NonSerializableCapture),
+			);
+		}
+	}
+}
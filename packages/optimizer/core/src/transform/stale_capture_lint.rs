@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use swc_common::{Span, Spanned};
+use swc_ecmascript::ast;
+use swc_ecmascript::visit::{Visit, VisitWith};
+
+use crate::collector::Id;
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::{IdPlusType, IdentType, QwikTransform};
+
+/// Every assignment and update of a binding, e.g. `count = 1`, `[a, b] = pair` or `i++`,
+/// and the bodies of the loops around them.
+#[derive(Default)]
+struct Reassignments {
+	writes: HashMap<Id, Vec<Span>>,
+	loop_bodies: Vec<Span>,
+}
+
+impl Reassignments {
+	fn add(&mut self, ident: &ast::Ident, span: Span) {
+		self.writes.entry(ident.to_id()).or_default().push(span);
+	}
+
+	fn add_loop(&mut self, body: &ast::Stmt) {
+		self.loop_bodies.push(body.span());
+	}
+}
+
+/// The identifiers a destructuring assignment writes to.
+struct AssignedIdents<'a> {
+	reassignments: &'a mut Reassignments,
+	span: Span,
+}
+
+impl Visit for AssignedIdents<'_> {
+	fn visit_binding_ident(&mut self, ident: &ast::BindingIdent) {
+		self.reassignments.add(&ident.id, self.span);
+	}
+
+	fn visit_expr(&mut self, _: &ast::Expr) {
+		// default values and computed keys are read, not written
+	}
+}
+
+impl Visit for Reassignments {
+	fn visit_assign_expr(&mut self, assign: &ast::AssignExpr) {
+		match &assign.left {
+			ast::AssignTarget::Simple(ast::SimpleAssignTarget::Ident(ident)) => {
+				self.add(&ident.id, assign.span);
+			}
+			ast::AssignTarget::Pat(pat) => pat.visit_with(&mut AssignedIdents {
+				reassignments: self,
+				span: assign.span,
+			}),
+			_ => {}
+		}
+		assign.visit_children_with(self);
+	}
+
+	fn visit_update_expr(&mut self, update: &ast::UpdateExpr) {
+		if let ast::Expr::Ident(ident) = &*update.arg {
+			self.add(ident, update.span);
+		}
+		update.visit_children_with(self);
+	}
+
+	fn visit_while_stmt(&mut self, stmt: &ast::WhileStmt) {
+		self.add_loop(&stmt.body);
+		stmt.visit_children_with(self);
+	}
+
+	fn visit_do_while_stmt(&mut self, stmt: &ast::DoWhileStmt) {
+		self.add_loop(&stmt.body);
+		stmt.visit_children_with(self);
+	}
+
+	fn visit_for_stmt(&mut self, stmt: &ast::ForStmt) {
+		self.add_loop(&stmt.body);
+		stmt.visit_children_with(self);
+	}
+
+	fn visit_for_in_stmt(&mut self, stmt: &ast::ForInStmt) {
+		self.add_loop(&stmt.body);
+		stmt.visit_children_with(self);
+	}
+
+	fn visit_for_of_stmt(&mut self, stmt: &ast::ForOfStmt) {
+		self.add_loop(&stmt.body);
+		stmt.visit_children_with(self);
+	}
+}
+
+impl QwikTransform<'_> {
+	pub(super) fn collect_reassignments(&mut self, program: &ast::Program) {
+		let mut reassignments = Reassignments::default();
+		program.visit_with(&mut reassignments);
+		self.reassignments = reassignments.writes;
+		self.loop_bodies = reassignments.loop_bodies;
+	}
+
+	/// Whether `write` runs in the same loop body as the QRL at `span`, the next iteration
+	/// creates a new QRL with the new value.
+	fn in_same_loop(&self, span: Span, write: Span) -> bool {
+		self.loop_bodies
+			.iter()
+			.any(|body| body.contains(span) && body.contains(write))
+	}
+
+	/// Warns when the segment at `span` captures a mutable binding that is assigned after the
+	/// QRL is created. The QRL keeps the value serialized when it was created, so later
+	/// assignments are not seen by it on the client. Assignments inside the segment are its own,
+	/// and assignments in the same loop body as the QRL reach the QRL of the next iteration.
+	pub(super) fn lint_stale_captures(
+		&self,
+		span: Span,
+		scoped_idents: &[Id],
+		decl_collect: &[IdPlusType],
+	) {
+		for id in scoped_idents {
+			let mutable = decl_collect
+				.iter()
+				.any(|(decl, ident_type)| decl == id && *ident_type == IdentType::Var(false));
+			let Some(later) = self
+				.reassignments
+				.get(id)
+				.filter(|_| mutable)
+				.and_then(|writes| {
+					writes
+						.iter()
+						.find(|write| write.lo >= span.hi && !self.in_same_loop(span, **write))
+				})
+			else {
+				continue;
+			};
+			let line = self.options.cm.lookup_char_pos(later.lo).line;
+			self.emit_span_warning_with_code(
+				span,
+				&format!(
+					"{} is captured by this QRL but assigned again on line {} after the QRL is created, the QRL keeps the value it had when it was created. Use a signal or a store to share the changes",
+					id.0, line
+				),
+				get_diagnostic_code(DiagnosticRule::StaleCapture),
+			);
+		}
+	}
+}
@@ -0,0 +1,280 @@
+use std::cell::Cell;
+
+use swc_common::errors::{Applicability, DiagnosticBuilder, DiagnosticId};
+use swc_common::Span;
+
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::transform::{QwikTransform, QwikTransformOptions};
+
+/// Silences the listed rules on the line after the comment
+const QWIK_DISABLE_NEXT_LINE_DIRECTIVE: &str = "@qwik-disable-next-line";
+/// Silences the listed rules in the whole file
+const QWIK_DISABLE_FILE_DIRECTIVE: &str = "@qwik-disable-file";
+/// Silences the listed rules until a matching `@qwik-enable` or the end of the file
+const QWIK_DISABLE_DIRECTIVE: &str = "@qwik-disable";
+/// Ends `@qwik-disable` ranges, all of them when no rule is listed
+const QWIK_ENABLE_DIRECTIVE: &str = "@qwik-enable";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SuppressionScope {
+	Line(usize),
+	/// Lines from `start` up to, but not including, `end`
+	Range {
+		start: usize,
+		end: Option<usize>,
+	},
+	File,
+}
+
+/// One rule of one directive. Rules may end with `*` to match every code with that prefix.
+#[derive(Debug)]
+struct Suppression {
+	directive: &'static str,
+	rule: String,
+	scope: SuppressionScope,
+	span: Span,
+	used: Cell<bool>,
+}
+
+impl Suppression {
+	fn covers(&self, line: usize) -> bool {
+		match self.scope {
+			SuppressionScope::Line(l) => l == line,
+			SuppressionScope::Range { start, end } => {
+				start <= line && end.is_none_or(|end| line < end)
+			}
+			SuppressionScope::File => true,
+		}
+	}
+
+	fn matches(&self, code: &str) -> bool {
+		match self.rule.strip_suffix('*') {
+			Some(prefix) => code.starts_with(prefix),
+			None => self.rule == code,
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Suppressions(Vec<Suppression>);
+
+impl<'a> QwikTransform<'a> {
+	/// Collects the suppression directives and removes their comments from the output.
+	pub(super) fn collect_suppressions(options: &QwikTransformOptions<'a>) -> Suppressions {
+		let Some(comments) = options.comments else {
+			return Suppressions::default();
+		};
+
+		let mut directives = vec![];
+		let (mut leading, mut trailing) = comments.borrow_all_mut();
+		for comments_map in [&mut *leading, &mut *trailing] {
+			for comments in comments_map.values_mut() {
+				comments.retain(|comment| match parse_directive(comment.text.as_ref()) {
+					Some((directive, rules)) => {
+						directives.push((comment.span, directive, rules));
+						false
+					}
+					None => true,
+				});
+			}
+		}
+		directives.sort_by_key(|(span, ..)| span.lo);
+
+		let mut suppressions: Vec<Suppression> = vec![];
+		for (span, directive, rules) in directives {
+			let line = |pos| options.cm.lookup_char_pos(pos).line;
+			let scope = match directive {
+				QWIK_ENABLE_DIRECTIVE => {
+					let end = line(span.lo);
+					for suppression in &mut suppressions {
+						let closes =
+							rules.is_empty() || rules.iter().any(|rule| suppression.matches(rule));
+						if let SuppressionScope::Range {
+							end: open @ None, ..
+						} = &mut suppression.scope
+						{
+							if closes {
+								*open = Some(end);
+							}
+						}
+					}
+					continue;
+				}
+				QWIK_DISABLE_NEXT_LINE_DIRECTIVE => SuppressionScope::Line(line(span.hi) + 1),
+				QWIK_DISABLE_FILE_DIRECTIVE => SuppressionScope::File,
+				_ => SuppressionScope::Range {
+					start: line(span.lo),
+					end: None,
+				},
+			};
+			suppressions.extend(rules.into_iter().map(|rule| Suppression {
+				directive,
+				rule,
+				scope,
+				span,
+				used: Cell::new(false),
+			}));
+		}
+		Suppressions(suppressions)
+	}
+
+	pub(super) fn is_diagnostic_disabled(&self, span: Span, code: &str) -> bool {
+		self.is_disabled_by_others(span, code, None)
+	}
+
+	/// Like `is_diagnostic_disabled`, but ignores `own`, so a directive can't silence itself.
+	fn is_disabled_by_others(&self, span: Span, code: &str, own: Option<&Suppression>) -> bool {
+		if span.lo.is_dummy() {
+			return false;
+		}
+
+		let line = self.options.cm.lookup_char_pos(span.lo).line;
+		let mut disabled = false;
+		for suppression in &self.suppressions.0 {
+			if own.is_some_and(|own| std::ptr::eq(own, suppression)) {
+				continue;
+			}
+			if suppression.covers(line) && suppression.matches(code) {
+				suppression.used.set(true);
+				disabled = true;
+			}
+		}
+		disabled
+	}
+
+	/// Warns about every directive rule that didn't silence any diagnostic, unless another
+	/// directive silences `unused-suppression` there.
+	pub fn report_unused_suppressions(&self) {
+		let code = get_diagnostic_code(DiagnosticRule::UnusedSuppression);
+		// Directives for `unused-suppression` only become used while the others are checked
+		let (own, others): (Vec<_>, Vec<_>) =
+			self.suppressions.0.iter().partition(|s| s.matches(code));
+		for suppression in others.into_iter().chain(own) {
+			if suppression.used.get()
+				|| self.is_disabled_by_others(suppression.span, code, Some(suppression))
+			{
+				continue;
+			}
+			swc_common::errors::HANDLER.with(|handler| {
+				handler
+					.struct_span_warn_with_code(
+						suppression.span,
+						&format!(
+							"{} {} doesn't suppress any diagnostic, remove it",
+							suppression.directive, suppression.rule
+						),
+						DiagnosticId::Error(code.into()),
+					)
+					.emit();
+			});
+		}
+	}
+
+	pub(super) fn emit_span_warning_with_code(&self, span: Span, message: &str, code: &str) {
+		self.emit_span_warning_with_fix(span, message, code, None);
+	}
+
+	pub(super) fn emit_span_warning_with_fix(
+		&self,
+		span: Span,
+		message: &str,
+		code: &str,
+		fix: Option<SuggestedFix>,
+	) {
+		if self.is_diagnostic_disabled(span, code) {
+			return;
+		}
+
+		swc_common::errors::HANDLER.with(|handler| {
+			emit_with_fix(
+				handler.struct_span_warn_with_code(span, message, DiagnosticId::Error(code.into())),
+				fix,
+			);
+		});
+	}
+
+	pub(super) fn emit_span_error_with_code(&self, span: Span, message: &str, code: &str) {
+		if self.is_diagnostic_disabled(span, code) {
+			return;
+		}
+
+		swc_common::errors::HANDLER.with(|handler| {
+			handler
+				.struct_span_err_with_code(span, message, DiagnosticId::Error(code.into()))
+				.emit();
+		});
+	}
+
+	pub(super) fn emit_error_with_fix(
+		&self,
+		span: Span,
+		message: &str,
+		code: &str,
+		fix: Option<SuggestedFix>,
+	) {
+		if self.is_diagnostic_disabled(span, code) {
+			return;
+		}
+
+		swc_common::errors::HANDLER.with(|handler| {
+			emit_with_fix(
+				handler.struct_span_err_with_code(span, message, DiagnosticId::Error(code.into())),
+				fix,
+			);
+		});
+	}
+}
+
+/// Edits that resolve a diagnostic, they end up in `Diagnostic.fixes`.
+pub(super) struct SuggestedFix {
+	pub message: String,
+	pub edits: Vec<(Span, String)>,
+	/// Whether the edits keep the behavior of the code
+	pub safe: bool,
+}
+
+fn emit_with_fix(mut builder: DiagnosticBuilder<'_>, fix: Option<SuggestedFix>) {
+	if let Some(fix) = fix {
+		builder.multipart_suggestion_with_applicability(
+			&fix.message,
+			fix.edits,
+			if fix.safe {
+				Applicability::MachineApplicable
+			} else {
+				Applicability::MaybeIncorrect
+			},
+		);
+	}
+	builder.emit();
+}
+
+/// The directive and its comma separated rules. Only `@qwik-enable` does anything without rules.
+fn parse_directive(comment: &str) -> Option<(&'static str, Vec<String>)> {
+	for line in comment.lines() {
+		let line = line.trim_start_matches(['*', ' ']).trim();
+		// Longest first, `@qwik-disable` is a prefix of the other two
+		for directive in [
+			QWIK_DISABLE_NEXT_LINE_DIRECTIVE,
+			QWIK_DISABLE_FILE_DIRECTIVE,
+			QWIK_DISABLE_DIRECTIVE,
+			QWIK_ENABLE_DIRECTIVE,
+		] {
+			let Some(rest) = line.strip_prefix(directive) else {
+				continue;
+			};
+			if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+				continue;
+			}
+
+			let rules = rest
+				.split(',')
+				.map(str::trim)
+				.filter(|rule| !rule.is_empty())
+				.map(ToOwned::to_owned)
+				.collect();
+			return Some((directive, rules));
+		}
+	}
+
+	None
+}
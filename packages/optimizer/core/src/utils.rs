@@ -1,16 +1,19 @@
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
 use swc_atoms::Atom;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct SourceLocation {
-	lo: usize,
-	hi: usize,
-	start_line: usize,
-	start_col: usize,
-	end_line: usize,
-	end_col: usize,
+	pub lo: usize,
+	pub hi: usize,
+	pub start_line: usize,
+	pub start_col: usize,
+	pub end_line: usize,
+	pub end_col: usize,
 }
 
 impl SourceLocation {
@@ -51,6 +54,95 @@ pub struct Diagnostic {
 	pub highlights: Option<Vec<SourceLocation>>,
 	pub suggestions: Option<Vec<String>>,
 	pub scope: DiagnosticScope,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub fixes: Vec<Fix>,
+}
+
+/// A change to the input file that resolves a diagnostic.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Fix {
+	pub message: String,
+	/// Safe fixes keep the behavior of the code and can be applied without review.
+	pub safe: bool,
+	pub edits: Vec<TextEdit>,
+}
+
+/// Replaces the text in `range` with `replacement`.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TextEdit {
+	pub range: EditRange,
+	pub replacement: String,
+}
+
+/// The text a `TextEdit` replaces.
+///
+/// Unlike `SourceLocation`, `start` and `end` are byte offsets into the input code and `end_col`
+/// is the column after the last replaced character, so an insertion has `start_col == end_col`.
+/// Lines and columns are 1-based.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EditRange {
+	pub start: usize,
+	pub end: usize,
+	pub start_line: usize,
+	pub start_col: usize,
+	pub end_line: usize,
+	pub end_col: usize,
+}
+
+impl EditRange {
+	pub fn from(source_map: &swc_common::SourceMap, span: swc_common::Span) -> Self {
+		let start = source_map.lookup_char_pos(span.lo);
+		let end = source_map.lookup_char_pos(span.hi);
+		Self {
+			start: source_map.lookup_byte_offset(span.lo).pos.0 as usize,
+			end: source_map.lookup_byte_offset(span.hi).pos.0 as usize,
+			start_line: start.line,
+			start_col: start.col_display + 1,
+			end_line: end.line,
+			end_col: end.col_display + 1,
+		}
+	}
+}
+
+/// Applies `fixes` to `code`, returning the new code and how many fixes were applied.
+///
+/// A fix with an edit that overlaps an edit of an earlier fix is skipped, running the optimizer
+/// again picks it up.
+pub fn apply_fixes<'a>(code: &str, fixes: impl IntoIterator<Item = &'a Fix>) -> (String, usize) {
+	let mut taken: Vec<(usize, usize)> = vec![];
+	let mut edits: Vec<&TextEdit> = vec![];
+	let mut applied = 0;
+	for fix in fixes {
+		let ranges: Vec<_> = fix
+			.edits
+			.iter()
+			.map(|edit| (edit.range.start, edit.range.end))
+			.collect();
+		let fits = ranges.iter().all(|&(lo, hi)| {
+			lo <= hi
+				&& hi <= code.len()
+				&& code.is_char_boundary(lo)
+				&& code.is_char_boundary(hi)
+				&& taken
+					.iter()
+					.all(|&taken| (hi <= taken.0 || lo >= taken.1) && taken != (lo, hi))
+		});
+		if fits {
+			taken.extend(ranges);
+			edits.extend(&fix.edits);
+			applied += 1;
+		}
+	}
+	// Back to front, so the offsets of the edits still to apply don't move
+	edits.sort_by_key(|edit| std::cmp::Reverse((edit.range.start, edit.range.end)));
+	let mut out = code.to_string();
+	for edit in edits {
+		out.replace_range(edit.range.start..edit.range.end, &edit.replacement);
+	}
+	(out, applied)
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
@@ -69,3 +161,41 @@ pub enum DiagnosticCategory {
 pub enum DiagnosticScope {
 	Optimizer,
 }
+
+/// Runs `f` for one input file, turning a panic into an error diagnostic for `file` so the rest
+/// of the batch still goes through. Only the napi binding is built with unwinding, release
+/// builds of the other targets (e.g. wasm and the CLI) abort on panic.
+pub fn catch_panic<T>(file: &str, f: impl FnOnce() -> T) -> Result<T, Box<Diagnostic>> {
+	catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+		let reason = payload
+			.downcast_ref::<&str>()
+			.map(|s| s.to_string())
+			.or_else(|| payload.downcast_ref::<String>().cloned())
+			.unwrap_or_else(|| "unknown panic".to_string());
+		Box::new(Diagnostic {
+			category: DiagnosticCategory::Error,
+			code: Some(get_diagnostic_code(DiagnosticRule::OptimizerPanic).to_string()),
+			file: file.into(),
+			message: format!(
+				"The optimizer crashed while processing this file: {}",
+				reason
+			),
+			highlights: None,
+			suggestions: None,
+			scope: DiagnosticScope::Optimizer,
+			fixes: vec![],
+		})
+	})
+}
+
+/// Returns the path without its file extension, using forward slashes.
+pub fn strip_extension(path: &str) -> String {
+	let p = Path::new(path);
+	match (p.parent(), p.file_stem()) {
+		(Some(parent), Some(stem)) if !parent.as_os_str().is_empty() => {
+			parent.join(stem).to_string_lossy().replace('\\', "/")
+		}
+		(_, Some(stem)) => stem.to_string_lossy().to_string(),
+		_ => path.to_string(),
+	}
+}
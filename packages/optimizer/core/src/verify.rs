@@ -0,0 +1,338 @@
+use crate::collector::{global_collect, GlobalCollect, Id};
+use crate::errors::{get_diagnostic_code, DiagnosticRule};
+use crate::parse::{SegmentAnalysis, TransformModule};
+use crate::utils::{strip_extension, Diagnostic, DiagnosticCategory, DiagnosticScope};
+use path_slash::PathBufExt;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use swc_atoms::Atom;
+use swc_common::{sync::Lrc, FileName, Mark, SourceMap};
+use swc_ecmascript::ast;
+use swc_ecmascript::parser::{lexer::Lexer, EsSyntax, Parser, StringInput, Syntax, TsSyntax};
+use swc_ecmascript::transforms::resolver;
+use swc_ecmascript::visit::{Visit, VisitMutWith, VisitWith};
+
+/// Names `resolver` left unresolved in `program`, i.e. globals or undeclared identifiers.
+/// Lowercase JSX tags are skipped, they name elements and not bindings.
+pub fn free_idents(program: &ast::Program, unresolved_mark: Mark) -> HashSet<Atom> {
+	let mut collector = FreeIdents {
+		unresolved_mark,
+		names: HashSet::new(),
+	};
+	program.visit_with(&mut collector);
+	collector.names
+}
+
+struct FreeIdents {
+	unresolved_mark: Mark,
+	names: HashSet<Atom>,
+}
+
+impl Visit for FreeIdents {
+	fn visit_ident(&mut self, ident: &ast::Ident) {
+		if ident.ctxt.outer() == self.unresolved_mark {
+			self.names.insert(ident.sym.clone());
+		}
+	}
+
+	/// Only the local side of `export { local as exported }` is a reference.
+	fn visit_export_named_specifier(&mut self, specifier: &ast::ExportNamedSpecifier) {
+		specifier.orig.visit_with(self);
+	}
+
+	fn visit_jsx_element_name(&mut self, name: &ast::JSXElementName) {
+		if let ast::JSXElementName::Ident(ident) = name {
+			if ident.sym.starts_with(|c: char| c.is_ascii_lowercase()) {
+				return;
+			}
+		}
+		name.visit_children_with(self);
+	}
+}
+
+/// What the emitted modules are checked against, taken from the input after `resolver`.
+pub struct VerifySource {
+	/// Identifiers that are free in the input, they may stay free in the output
+	globals: HashSet<Atom>,
+	/// Inputs that use `_captures` themselves, e.g. pre-built library code, don't get their
+	/// capture counts checked
+	check_captures: bool,
+}
+
+impl VerifySource {
+	pub fn new(program: &ast::Program, unresolved_mark: Mark, collect: &GlobalCollect) -> Self {
+		Self {
+			globals: free_idents(program, unresolved_mark),
+			check_captures: !collect
+				.imports
+				.values()
+				.any(|import| import.specifier == *"_captures"),
+		}
+	}
+}
+
+/// Re-parses the modules emitted for `origin` and reports, as internal errors:
+/// - identifiers that are neither declared, imported nor free in the input
+/// - `_captures[i]` reads past the captures of a segment, and `.w([...])` calls that pass a
+///   different number of captures than the segment reads
+/// - `qrl(() => import(path))` calls whose `path` is not one of the emitted modules
+pub fn verify_modules(
+	origin: &str,
+	modules: &[TransformModule],
+	source: &VerifySource,
+) -> Vec<Diagnostic> {
+	let paths: HashSet<String> = modules
+		.iter()
+		.map(|module| strip_extension(&module.path))
+		.collect();
+	let captures: HashMap<&Atom, usize> = modules
+		.iter()
+		.filter_map(|module| module.segment.as_ref())
+		.map(|segment| (&segment.name, capture_count(segment)))
+		.collect();
+
+	let mut problems = vec![];
+	for module in modules {
+		let (program, unresolved_mark) = match parse_emitted(&module.code, &module.path) {
+			Ok(parsed) => parsed,
+			Err(err) => {
+				problems.push(format!("{} doesn't parse: {}", module.path, err));
+				continue;
+			}
+		};
+		let mut checker = ModuleChecker::new(module, &program, &paths, &captures);
+		checker.check_captures = source.check_captures;
+		program.visit_with(&mut checker);
+		problems.append(&mut checker.problems);
+
+		let free = free_idents(&program, unresolved_mark);
+		let mut undeclared: Vec<_> = free
+			.iter()
+			.filter(|name| !source.globals.contains(*name))
+			.collect();
+		undeclared.sort();
+		for name in undeclared {
+			problems.push(format!(
+				"{} references `{}`, which is not declared or imported",
+				module.path, name
+			));
+		}
+	}
+
+	problems
+		.into_iter()
+		.map(|problem| Diagnostic {
+			category: DiagnosticCategory::Error,
+			code: Some(get_diagnostic_code(DiagnosticRule::InternalError).to_string()),
+			file: origin.into(),
+			message: format!("The optimizer emitted a broken module: {}", problem),
+			highlights: None,
+			suggestions: None,
+			scope: DiagnosticScope::Optimizer,
+			fixes: vec![],
+		})
+		.collect()
+}
+
+/// The number of captures `.w([...])` passes to the segment, one per scoped identifier.
+fn capture_count(segment: &SegmentAnalysis) -> usize {
+	segment.capture_names.as_ref().map_or(0, Vec::len)
+}
+
+/// The parsed and resolved module, with the mark `resolver` gave to free identifiers.
+fn parse_emitted(code: &str, path: &str) -> Result<(ast::Program, Mark), String> {
+	let source_map: Lrc<SourceMap> = Default::default();
+	let file =
+		source_map.new_source_file(FileName::Custom(path.to_string()).into(), code.to_string());
+	let syntax = match Path::new(path).extension().and_then(|e| e.to_str()) {
+		Some("ts" | "mts") => Syntax::Typescript(TsSyntax::default()),
+		Some("tsx" | "mtsx") => Syntax::Typescript(TsSyntax {
+			tsx: true,
+			..Default::default()
+		}),
+		_ => Syntax::Es(EsSyntax {
+			jsx: true,
+			..Default::default()
+		}),
+	};
+	let lexer = Lexer::new(syntax, Default::default(), StringInput::from(&*file), None);
+	let mut program = Parser::new_from(lexer)
+		.parse_program()
+		.map_err(|err| format!("{:?}", err.kind()))?;
+	let unresolved_mark = Mark::new();
+	program.visit_mut_with(&mut resolver(
+		unresolved_mark,
+		Mark::new(),
+		matches!(syntax, Syntax::Typescript(_)),
+	));
+	Ok((program, unresolved_mark))
+}
+
+struct ModuleChecker<'a> {
+	module: &'a TransformModule,
+	paths: &'a HashSet<String>,
+	captures: &'a HashMap<&'a Atom, usize>,
+	/// Locals bound to `_captures`, `qrl` and `qrlDEV` imports
+	captures_ids: HashSet<Id>,
+	qrl_ids: HashSet<Id>,
+	/// QRL constants and the segment they point at
+	qrl_symbols: HashMap<Id, Atom>,
+	check_captures: bool,
+	problems: Vec<String>,
+}
+
+impl<'a> ModuleChecker<'a> {
+	fn new(
+		module: &'a TransformModule,
+		program: &ast::Program,
+		paths: &'a HashSet<String>,
+		captures: &'a HashMap<&'a Atom, usize>,
+	) -> Self {
+		let collect = global_collect(program);
+		let imported = |specifier: &str| -> HashSet<Id> {
+			collect
+				.imports
+				.iter()
+				.filter(|(_, import)| import.specifier == *specifier)
+				.map(|(id, _)| id.clone())
+				.collect()
+		};
+		Self {
+			module,
+			paths,
+			captures,
+			captures_ids: imported("_captures"),
+			qrl_ids: imported("qrl")
+				.into_iter()
+				.chain(imported("qrlDEV"))
+				.collect(),
+			qrl_symbols: HashMap::new(),
+			check_captures: true,
+			problems: vec![],
+		}
+	}
+
+	/// The segment name of a `qrl(() => import(path), "name")` call, after checking that `path`
+	/// was emitted.
+	fn check_qrl_call(&mut self, call: &ast::CallExpr) -> Option<Atom> {
+		let ast::Callee::Expr(box ast::Expr::Ident(callee)) = &call.callee else {
+			return None;
+		};
+		if !self.qrl_ids.contains(&callee.to_id()) {
+			return None;
+		}
+		if let Some(src) = call
+			.args
+			.first()
+			.and_then(|arg| dynamic_import_source(&arg.expr))
+		{
+			let target = resolve_relative(&self.module.path, &src);
+			if !self.paths.contains(&strip_extension(&target)) {
+				self.problems.push(format!(
+					"{} loads a QRL from {}, which was not emitted",
+					self.module.path, src
+				));
+			}
+		}
+		match call.args.get(1).map(|arg| &*arg.expr) {
+			Some(ast::Expr::Lit(ast::Lit::Str(name))) => Some(name.value.clone()),
+			_ => None,
+		}
+	}
+}
+
+impl Visit for ModuleChecker<'_> {
+	fn visit_var_declarator(&mut self, decl: &ast::VarDeclarator) {
+		if let (ast::Pat::Ident(ident), Some(box ast::Expr::Call(call))) = (&decl.name, &decl.init)
+		{
+			if let Some(symbol) = self.check_qrl_call(call) {
+				self.qrl_symbols.insert(ident.to_id(), symbol);
+				call.visit_children_with(self);
+				return;
+			}
+		}
+		decl.visit_children_with(self);
+	}
+
+	fn visit_call_expr(&mut self, call: &ast::CallExpr) {
+		self.check_qrl_call(call);
+		if let ast::Callee::Expr(box ast::Expr::Member(ast::MemberExpr {
+			obj: box ast::Expr::Ident(obj),
+			prop: ast::MemberProp::Ident(prop),
+			..
+		})) = &call.callee
+		{
+			if self.check_captures && prop.sym == *"w" {
+				let symbol = self.qrl_symbols.get(&obj.to_id());
+				let expected = symbol.and_then(|symbol| self.captures.get(symbol));
+				if let (Some(symbol), Some(expected), Some(ast::Expr::Array(array))) =
+					(symbol, expected, call.args.first().map(|arg| &*arg.expr))
+				{
+					if array.elems.len() != *expected {
+						self.problems.push(format!(
+							"{} passes {} captures to {}, which reads {}",
+							self.module.path,
+							array.elems.len(),
+							symbol,
+							expected
+						));
+					}
+				}
+			}
+		}
+		call.visit_children_with(self);
+	}
+
+	fn visit_member_expr(&mut self, member: &ast::MemberExpr) {
+		if let (ast::Expr::Ident(obj), ast::MemberProp::Computed(prop)) =
+			(&*member.obj, &member.prop)
+		{
+			// Inlined QRLs in the root are given their captures where they are created
+			let segment = self.module.segment.as_ref().filter(|_| self.check_captures);
+			if let (Some(segment), true) = (segment, self.captures_ids.contains(&obj.to_id())) {
+				let count = capture_count(segment);
+				match &*prop.expr {
+					ast::Expr::Lit(ast::Lit::Num(index)) if (index.value as usize) < count => {}
+					_ => self.problems.push(format!(
+						"{} reads a capture past the {} it was given",
+						self.module.path, count
+					)),
+				}
+			}
+		}
+		member.visit_children_with(self);
+	}
+}
+
+fn dynamic_import_source(expr: &ast::Expr) -> Option<Atom> {
+	let ast::Expr::Arrow(arrow) = expr else {
+		return None;
+	};
+	let ast::BlockStmtOrExpr::Expr(box ast::Expr::Call(call)) = &*arrow.body else {
+		return None;
+	};
+	if !matches!(call.callee, ast::Callee::Import(_)) {
+		return None;
+	}
+	match call.args.first().map(|arg| &*arg.expr) {
+		Some(ast::Expr::Lit(ast::Lit::Str(src))) => Some(src.value.clone()),
+		_ => None,
+	}
+}
+
+/// `src` resolved against the directory of `importer`, both relative to `src_dir`.
+fn resolve_relative(importer: &str, src: &str) -> String {
+	let mut path = Path::new(importer)
+		.parent()
+		.map_or_else(PathBuf::new, Path::to_path_buf);
+	for component in Path::new(src).components() {
+		match component {
+			Component::ParentDir => {
+				path.pop();
+			}
+			Component::Normal(part) => path.push(part),
+			_ => {}
+		}
+	}
+	path.to_slash_lossy().to_string()
+}
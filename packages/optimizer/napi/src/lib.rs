@@ -6,7 +6,9 @@ extern crate napi;
 #[macro_use]
 extern crate napi_derive;
 
-use napi::{CallContext, JsObject, Result};
+use napi::{CallContext, Env, JsObject, JsUnknown, Result, Status};
+use qwik_core::OptimizerError;
+use serde::Serialize;
 use tokio::task;
 
 #[cfg(windows)]
@@ -16,26 +18,94 @@ static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
 #[allow(clippy::needless_pass_by_value)]
 #[js_function(1)]
 fn transform_modules(ctx: CallContext) -> Result<JsObject> {
-	let opts = ctx.get::<JsObject>(0)?;
-	let config: qwik_core::TransformModulesOptions = ctx.env.from_js_value(opts)?;
+	let config = options(&ctx)?;
 
 	ctx.env.execute_tokio_future(
 		async move {
 			// Spawn the CPU-intensive work onto a separate thread in the thread pool
-			let result = task::spawn_blocking(move || qwik_core::transform_modules(config))
+			task::spawn_blocking(move || qwik_core::transform_modules(config))
 				.await
-				.unwrap()
-				.map_err(|e| napi::Error::from_reason(e.to_string()))?;
+				.map_err(|e| napi::Error::from_reason(e.to_string()))
+		},
+		to_js_result,
+	)
+}
+
+#[allow(clippy::needless_pass_by_value)]
+#[js_function(1)]
+fn analyze_modules(ctx: CallContext) -> Result<JsObject> {
+	let config = options(&ctx)?;
 
-			Ok(result)
+	ctx.env.execute_tokio_future(
+		async move {
+			task::spawn_blocking(move || qwik_core::analyze_modules(config))
+				.await
+				.map_err(|e| napi::Error::from_reason(e.to_string()))
 		},
-		|env, result| env.to_js_value(&result),
+		to_js_result,
 	)
 }
 
+#[allow(clippy::needless_pass_by_value)]
+#[js_function(1)]
+fn module_info(ctx: CallContext) -> Result<JsObject> {
+	let config = options(&ctx)?;
+
+	ctx.env.execute_tokio_future(
+		async move {
+			task::spawn_blocking(move || qwik_core::module_info(config))
+				.await
+				.map_err(|e| napi::Error::from_reason(e.to_string()))
+		},
+		to_js_result,
+	)
+}
+
+/// The options passed from JS. Unknown fields and values that don't deserialize reject like the
+/// problems `validate` finds, with an `invalidOptions` error.
+fn options(ctx: &CallContext) -> Result<qwik_core::TransformModulesOptions> {
+	let opts = ctx.get::<JsObject>(0)?;
+	match ctx.env.from_js_value(opts) {
+		Ok(config) => Ok(config),
+		Err(err) => {
+			let err = OptimizerError::InvalidOptions {
+				message: err.reason,
+			};
+			Err(to_js_error(ctx.env, &err)?)
+		}
+	}
+}
+
+/// Resolves with the output, or rejects with an `Error` that carries the `kind` of the
+/// `OptimizerError` and the `file` it is about. Invalid options reject with the `InvalidArg` code.
+fn to_js_result<T: Serialize>(
+	env: &mut Env,
+	result: std::result::Result<T, OptimizerError>,
+) -> Result<JsUnknown> {
+	match result {
+		Ok(output) => env.to_js_value(&output),
+		Err(err) => Err(to_js_error(env, &err)?),
+	}
+}
+
+fn to_js_error(env: &Env, err: &OptimizerError) -> Result<napi::Error> {
+	let status = match err {
+		OptimizerError::InvalidOptions { .. } => Status::InvalidArg,
+		_ => Status::GenericFailure,
+	};
+	let mut js_err = env.create_error(napi::Error::new(status, err.to_string()))?;
+	js_err.set_named_property("kind", env.create_string(err.kind())?)?;
+	if let Some(file) = err.file() {
+		js_err.set_named_property("file", env.create_string(file)?)?;
+	}
+	Ok(napi::Error::from(js_err.into_unknown()))
+}
+
 #[module_exports]
 fn init(mut exports: JsObject) -> Result<()> {
 	exports.create_named_method("transform_modules", transform_modules)?;
+	exports.create_named_method("analyze_modules", analyze_modules)?;
+	exports.create_named_method("module_info", module_info)?;
 
 	Ok(())
 }
@@ -10,6 +10,12 @@ export const createOptimizer = async (optimizerOptions: OptimizerOptions = {}) =
     async transformModules(opts: TransformModulesOptions) {
       return transformModules(binding, opts);
     },
+    async analyzeModules(opts: TransformModulesOptions) {
+      return binding.analyze_modules(convertOptions(opts));
+    },
+    async moduleInfo(opts: TransformModulesOptions) {
+      return binding.module_info(convertOptions(opts));
+    },
     sys,
   };
 
@@ -21,6 +27,10 @@ const transformModules = (binding: PlatformBinding, opts: TransformModulesOption
   return binding.transform_modules(convertOptions(opts));
 };
 
+/**
+ * Every option is passed to the binding, which rejects unknown ones with an `invalidOptions` error
+ * that names them.
+ */
 const convertOptions = (opts: any) => {
   const output: any = {
     minify: 'simplify',
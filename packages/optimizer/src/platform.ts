@@ -1,4 +1,6 @@
 import type {
+  AnalyzeOutput,
+  ModuleInfoOutput,
   OptimizerSystem,
   SystemEnvironment,
   TransformModuleInput,
@@ -157,6 +159,8 @@ export async function loadPlatformBinding(sys: OptimizerSystem) {
 export interface PlatformBinding {
   transform_fs?: (opts: any) => TransformOutput;
   transform_modules: (opts: any) => Promise<TransformOutput>;
+  analyze_modules: (opts: any) => Promise<AnalyzeOutput>;
+  module_info: (opts: any) => Promise<ModuleInfoOutput>;
 }
 
 const getEnv = (): SystemEnvironment => {
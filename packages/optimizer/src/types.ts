@@ -3,6 +3,18 @@ export interface Optimizer {
   /** Transforms the input code string, does not access the file system. */
   transformModules(opts: TransformModulesOptions): Promise<TransformOutput>;
 
+  /**
+   * Collects segment metadata, imports, exports and diagnostics without generating any code, does
+   * not access the file system.
+   */
+  analyzeModules(opts: TransformModulesOptions): Promise<AnalyzeOutput>;
+
+  /**
+   * Parses the inputs and returns their imports and exports, without transforming them. Only
+   * `srcDir` and `input` are used.
+   */
+  moduleInfo(opts: TransformModulesOptions): Promise<ModuleInfoOutput>;
+
   /** Optimizer system use. This can be updated with a custom file system. */
   sys: OptimizerSystem;
 }
@@ -52,6 +64,42 @@ export interface TransformOptions {
   stripCtxName?: string[];
   stripEventHandlers?: boolean;
   isServer?: boolean;
+  /** Collect per-file pass timings and counters into `TransformOutput.stats` */
+  stats?: boolean;
+  /** Record why segments captured, migrated and hoisted what they did into `TransformOutput.explain` */
+  explain?: boolean;
+  /** Measure the raw, gzip and brotli size of every emitted module into `TransformOutput.sizeReport` */
+  sizeReport?: boolean;
+  /** Capture the code after every pass into `TransformOutput.debugDumps` */
+  debugDump?: DebugDumpOptions;
+  /** Re-parse every emitted module and report broken ones as `internal-error` diagnostics */
+  verify?: boolean;
+  /** Severity overrides keyed by diagnostic code, e.g. `{ C05: 'warn' }` */
+  rules?: Record<string, RuleSeverity>;
+}
+
+/** @public */
+export type RuleSeverity = 'off' | 'warn' | 'error';
+
+/** @public */
+export interface OptimizerError extends Error {
+  /** Only `invalidOptions` fails the whole call, the others are about `file` */
+  kind: 'path' | 'codegen' | 'transform' | 'invalidOptions';
+  file?: string;
+}
+
+/** @public */
+export interface DebugDumpOptions {
+  /**
+   * Also serialize the AST after every pass as JSON. Only available in optimizer builds with the
+   * `ast-dump` cargo feature, the published binaries reject it.
+   */
+  ast?: boolean;
+  /**
+   * Write the dumps into this directory instead of returning them. Not supported by the wasm
+   * optimizer.
+   */
+  outDir?: string;
 }
 
 /** @public */
@@ -76,8 +124,200 @@ export interface TransformOutput {
   diagnostics: Diagnostic[];
   isTypeScript: boolean;
   isJsx: boolean;
+  /** Only present when the `stats` option is enabled */
+  stats?: TransformStats[];
+  /** Only present when the `explain` option is enabled */
+  explain?: ExplainReport[];
+  /** Only present when the `debugDump` option is enabled without an `outDir` */
+  debugDumps?: PassDump[];
+  /** Only present when the `sizeReport` option is enabled */
+  sizeReport?: SizeReport;
+}
+
+/** @public */
+export interface AnalyzeOutput {
+  modules: ModuleAnalysis[];
+  diagnostics: Diagnostic[];
+  isTypeScript: boolean;
+  isJsx: boolean;
+  /** Only present when the `stats` option is enabled */
+  stats?: TransformStats[];
+}
+
+/** @public */
+export interface ModuleAnalysis {
+  /** Path of the input file, relative to `srcDir` */
+  path: string;
+  /** Segment metadata, `size` is always 0 since no code is generated */
+  segments: SegmentAnalysis[];
+  /** Sources of the static and dynamic imports of the root module */
+  imports: string[];
+  /** Names exported by the root module */
+  exports: string[];
+}
+
+/** @public */
+export interface ModuleInfoOutput {
+  modules: ModuleInfo[];
+  diagnostics: Diagnostic[];
+}
+
+/** @public */
+export interface ModuleInfo {
+  /** Path of the input file, relative to `srcDir` */
+  path: string;
+  imports: ModuleImport[];
+  /** Sources of `import('...')` calls with a string literal argument */
+  dynamicImports: string[];
+  exports: ModuleExport[];
+  /** Sources of `export ... from '...'`, which `exports` doesn't list */
+  reexports: string[];
+  /** Names declared at the top level without `export`, exported ones are in `exports` */
+  rootDeclarations: string[];
+}
+
+/** @public */
+export interface ModuleImport {
+  /** The local binding */
+  local: string;
+  source: string;
+  /** The imported name, `default` or `*` for namespace imports */
+  specifier: string;
+  kind: 'named' | 'default' | 'all';
+}
+
+/** @public */
+export interface ModuleExport {
+  /** The local binding, `null` for `export default <expression>` */
+  local: string | null;
+  /** The names the binding is exported as */
+  exported: string[];
+}
+
+/** @public */
+export interface PassDump {
+  path: string;
+  /** The segment symbol, `null` for the root module */
+  segment: string | null;
+  /** Position of the dump among the dumps of the same module */
+  index: number;
+  pass: string;
+  code: string;
+  ast?: string;
+}
+
+/** @public */
+export interface TransformStats {
+  path: string;
+  passes: PassTiming[];
+  totalMs: number;
+  segmentsCreated: number;
+  varsMigrated: number;
+  importsDropped: number;
+}
+
+/** @public */
+export interface PassTiming {
+  name: string;
+  durationMs: number;
+  runs: number;
+}
+
+/** @public */
+export interface ModuleSizes {
+  raw: number;
+  gzip: number;
+  brotli: number;
+}
+
+/** @public */
+export interface ModuleSizeEntry extends ModuleSizes {
+  path: string;
+  /** The segment symbol, `null` for root modules */
+  segment: string | null;
+  /** The entry the module is bundled into, the module path itself when it is its own entry */
+  entry: string;
+  origin: string;
+  /** Display name of the outermost `component$` segment the module belongs to */
+  component: string | null;
 }
 
+/** @public */
+export interface SizeReport {
+  modules: ModuleSizeEntry[];
+  entries: Record<string, ModuleSizes>;
+  origins: Record<string, ModuleSizes>;
+  components: Record<string, ModuleSizes>;
+  total: ModuleSizes;
+}
+
+/** @public */
+export interface ExplainReport {
+  path: string;
+  /** Decisions made outside of any segment, e.g. JSX props in the root module */
+  root: ExplainDecision[];
+  segments: SegmentExplain[];
+}
+
+/** @public */
+export interface SegmentExplain {
+  name: string;
+  displayName: string;
+  decisions: ExplainDecision[];
+}
+
+/** @public */
+export type ExplainDecision =
+  | {
+      kind: 'capture';
+      name: string;
+      captured: boolean;
+      reason:
+        | 'declaredInScope'
+        | 'param'
+        | 'functionOrClass'
+        | 'notAFunction'
+        | 'import'
+        | 'rootDeclaration'
+        | 'notInScope';
+    }
+  | {
+      kind: 'migration';
+      name: string;
+      migrated: boolean;
+      reason:
+        | 'singleSegment'
+        | 'transitiveDependency'
+        | 'usedByMultipleSegments'
+        | 'usedByRoot'
+        | 'exported'
+        | 'imported'
+        | 'rootDependency';
+    }
+  | {
+      kind: 'hoist';
+      hoisted: boolean;
+      currentDepth?: number;
+      targetDepth?: number;
+      reason: 'noCaptures' | 'notInLoop' | 'componentQrl' | 'componentScope' | 'outermostCaptureScope';
+    }
+  | {
+      kind: 'jsxProp';
+      name: string;
+      target: 'var' | 'const';
+      reason:
+        | 'constExpression'
+        | 'mutableExpression'
+        | 'constCaptures'
+        | 'mutableCaptures'
+        | 'getter'
+        | 'iterationVariable'
+        | 'spreadProps'
+        | 'refOrSlot'
+        | 'shorthand';
+      location: SourceLocation | null;
+    };
+
 /** @public */
 export interface SegmentAnalysis {
   origin: string;
@@ -92,10 +332,33 @@ export interface SegmentAnalysis {
   ctxName: string;
   captures: boolean;
   loc: [number, number];
+  /** Line/column range of the segment in the original source */
+  location?: SourceLocation;
   /** The parameter names if it's a function with parameters */
   paramNames?: string[];
   /** The transformed names of scoped variables, if any */
   captureNames?: string[];
+  /** The captured identifiers together with how they were declared */
+  captured?: SegmentCapture[];
+  /** The imports the segment code depends on */
+  imports?: SegmentImport[];
+  /** Byte length of the emitted segment code */
+  size: number;
+  /** All enclosing segments, outermost first */
+  parents?: string[];
+}
+
+/** @public */
+export interface SegmentCapture {
+  name: string;
+  /** `{ var: true }` for const bindings, `{ var: false }` for let/var bindings */
+  kind: { var: boolean } | 'fn' | 'class';
+}
+
+/** @public */
+export interface SegmentImport {
+  source: string;
+  specifier: string;
 }
 
 // RESULT OUTPUT ***************
@@ -122,6 +385,35 @@ export interface Diagnostic {
   message: string;
   highlights: SourceLocation[] | null;
   suggestions: string[] | null;
+  /** Edits that resolve the diagnostic, absent when there are none */
+  fixes?: Fix[];
+}
+
+/** @public */
+export interface Fix {
+  message: string;
+  /** Safe fixes keep the behavior of the code and can be applied without review */
+  safe: boolean;
+  edits: TextEdit[];
+}
+
+/** @public */
+export interface TextEdit {
+  range: EditRange;
+  replacement: string;
+}
+
+/** @public */
+export interface EditRange {
+  /** Byte offset into the input code */
+  start: number;
+  /** Byte offset into the input code, equal to `start` for insertions */
+  end: number;
+  startLine: number;
+  startCol: number;
+  endLine: number;
+  /** Column after the last replaced character */
+  endCol: number;
 }
 
 /** @public */
@@ -0,0 +1,345 @@
+#![deny(clippy::all)]
+#![deny(clippy::perf)]
+#![deny(clippy::nursery)]
+
+use qwik_core::{
+	DiagnosticCategory, RootProgram, TransformModulesOptions, TransformOutput,
+	TransformProgramInput,
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use swc_core::common::comments::{Comment, CommentKind, Comments, SingleThreadedComments};
+use swc_core::common::errors::HANDLER;
+use swc_core::common::plugin::metadata::TransformPluginMetadataContextKind;
+use swc_core::common::{
+	sync::Lrc, BytePos, Mark, SourceFile, SourceMap, SourceMapper, Span, Spanned, SyntaxContext,
+};
+use swc_core::ecma::ast::Program;
+use swc_core::ecma::transforms::base::resolver;
+use swc_core::ecma::visit::{Visit, VisitMut, VisitMutWith, VisitWith};
+use swc_core::plugin::{plugin_transform, proxies::TransformPluginProgramMetadata};
+
+/// Rewrites the root module in place. Segment modules can't be handed back to swc, so they are
+/// written to `segmentsDir` together with a `<path>.qwik.json` manifest of the whole output.
+///
+/// The plugin config is `TransformModulesOptions` without `input`, plus an optional `segmentsDir`.
+#[plugin_transform]
+pub fn process_transform(program: Program, metadata: TransformPluginProgramMetadata) -> Program {
+	let config = metadata.get_transform_plugin_config();
+	let (options, segments_dir) = match parse_config(config.as_deref()) {
+		Ok(config) => config,
+		Err(err) => {
+			report_error(&format!("Invalid qwik-swc-plugin config: {}", err));
+			return program;
+		}
+	};
+
+	let span = program.span();
+	if span.is_dummy() {
+		return program;
+	}
+	let host_file = metadata.source_map.lookup_char_pos(span.lo).file;
+	let filename = metadata
+		.get_context(&TransformPluginMetadataContextKind::Filename)
+		.unwrap_or_else(|| host_file.name.to_string());
+	let path = Path::new(&filename)
+		.strip_prefix(&options.src_dir)
+		.map_or_else(|_| filename.clone(), |p| p.to_string_lossy().to_string());
+
+	let plugin = Plugin::new(&host_file);
+	let output = plugin.transform(program, options, path, metadata.comments.as_ref());
+	plugin.report(&output, &filename);
+	if let Some(dir) = segments_dir {
+		if let Err(err) = write_segments(Path::new(&dir), &output) {
+			report_error(&format!("Writing Qwik segments to {}: {}", dir, err));
+		}
+	} else if output.modules.iter().any(|m| m.segment.is_some()) {
+		HANDLER.with(|handler| {
+			handler
+				.struct_warn(&format!(
+					"{} has segments, but no segmentsDir is configured to write them to",
+					filename
+				))
+				.emit()
+		});
+	}
+
+	let Some(root) = output.modules.into_iter().find_map(|m| m.program) else {
+		return Program::Module(Default::default());
+	};
+	plugin.finish(root, &metadata)
+}
+
+fn parse_config(config: Option<&str>) -> Result<(TransformModulesOptions, Option<String>), String> {
+	let mut value: Value =
+		serde_json::from_str(config.unwrap_or("{}")).map_err(|e| e.to_string())?;
+	let object = value
+		.as_object_mut()
+		.ok_or_else(|| "expected an object".to_string())?;
+	let segments_dir = match object.remove("segmentsDir") {
+		Some(Value::String(dir)) => Some(dir),
+		Some(Value::Null) | None => None,
+		Some(_) => return Err("segmentsDir must be a string".into()),
+	};
+	let options: TransformModulesOptions =
+		serde_json::from_value(value).map_err(|e| e.to_string())?;
+	options.validate().map_err(|e| e.to_string())?;
+	Ok((options, segments_dir))
+}
+
+fn report_error(message: &str) {
+	HANDLER.with(|handler| handler.struct_err(message).emit());
+}
+
+/// The optimizer works on its own copy of the file, which starts at a different `BytePos`
+/// than the host's. Spans are moved into the copy before the transform and back afterwards.
+struct Plugin {
+	source_map: Lrc<SourceMap>,
+	host_start: BytePos,
+	local_start: BytePos,
+}
+
+impl Plugin {
+	fn new(host_file: &SourceFile) -> Self {
+		let source_map: Lrc<SourceMap> = Default::default();
+		let local_file = source_map.new_source_file(host_file.name.clone(), host_file.src.clone());
+		Self {
+			source_map,
+			host_start: host_file.start_pos,
+			local_start: local_file.start_pos,
+		}
+	}
+
+	/// The host's comments are moved to the optimizer, which hands the ones of the root back in
+	/// `finish`. Directive comments are dropped on the way, like with the other bindings.
+	fn transform(
+		&self,
+		mut program: Program,
+		options: TransformModulesOptions,
+		path: String,
+		host_comments: Option<&impl Comments>,
+	) -> TransformOutput {
+		let comments = SingleThreadedComments::default();
+		if let Some(host_comments) = host_comments {
+			let mut positions = Positions::default();
+			program.visit_with(&mut positions);
+			for pos in positions.0 {
+				let local = rebase(pos, self.host_start, self.local_start);
+				if let Some(list) = host_comments.take_leading(pos) {
+					comments.add_leading_comments(
+						local,
+						list.into_iter().map(|c| self.to_local(c)).collect(),
+					);
+				}
+				if let Some(list) = host_comments.take_trailing(pos) {
+					comments.add_trailing_comments(
+						local,
+						list.into_iter().map(|c| self.to_local(c)).collect(),
+					);
+				}
+			}
+		}
+		program.visit_mut_with(&mut Rebase {
+			from: self.host_start,
+			to: self.local_start,
+		});
+		let input = TransformProgramInput {
+			path,
+			dev_path: None,
+			program,
+			source_map: Lrc::clone(&self.source_map),
+			comments,
+		};
+		// Per-input failures are diagnostics, the options were validated with the config
+		qwik_core::transform_programs(options, vec![input]).unwrap_or_else(|err| {
+			report_error(&format!("Qwik optimizer failed: {}", err));
+			TransformOutput::new()
+		})
+	}
+
+	/// Forwards the optimizer diagnostics to swc, pointing at the host's copy of the file.
+	fn report(&self, output: &TransformOutput, filename: &str) {
+		HANDLER.with(|handler| {
+			for diagnostic in &output.diagnostics {
+				let mut builder = match diagnostic.category {
+					DiagnosticCategory::Warning => handler.struct_warn(&diagnostic.message),
+					DiagnosticCategory::SourceError if filename.contains("/node_modules/") => {
+						handler.struct_warn(&diagnostic.message)
+					}
+					DiagnosticCategory::Error | DiagnosticCategory::SourceError => {
+						handler.struct_err(&diagnostic.message)
+					}
+				};
+				if let Some(loc) = diagnostic.highlights.as_ref().and_then(|h| h.first()) {
+					builder.set_span(Span::new(
+						self.to_host(BytePos(loc.lo as u32)),
+						self.to_host(BytePos(loc.hi as u32)),
+					));
+				}
+				builder.emit();
+			}
+		});
+	}
+
+	/// Moves the root back to the host's positions and resolves it against the host's marks.
+	/// The comments of the root, the ones `transform` took from the host included, go back to it.
+	fn finish(&self, root: RootProgram, metadata: &TransformPluginProgramMetadata) -> Program {
+		let mut program = root.program;
+		program.visit_mut_with(&mut Rebase {
+			from: self.local_start,
+			to: self.host_start,
+		});
+		program.visit_mut_with(&mut resolver(metadata.unresolved_mark, Mark::new(), false));
+
+		if let Some(comments) = &metadata.comments {
+			for (pos, list) in root.leading_comments {
+				let pos = self.to_host(pos);
+				if list.iter().all(is_pure_comment) {
+					comments.add_pure_comment(pos);
+				} else {
+					comments.add_leading_comments(pos, list);
+				}
+			}
+			for (pos, list) in root.trailing_comments {
+				comments.add_trailing_comments(self.to_host(pos), list);
+			}
+		}
+		program
+	}
+
+	fn to_host(&self, pos: BytePos) -> BytePos {
+		rebase(pos, self.local_start, self.host_start)
+	}
+
+	fn to_local(&self, mut comment: Comment) -> Comment {
+		comment.span.lo = rebase(comment.span.lo, self.host_start, self.local_start);
+		comment.span.hi = rebase(comment.span.hi, self.host_start, self.local_start);
+		comment
+	}
+}
+
+fn rebase(pos: BytePos, from: BytePos, to: BytePos) -> BytePos {
+	if pos.is_dummy() || pos.is_reserved_for_comments() {
+		pos
+	} else {
+		to + (pos - from)
+	}
+}
+
+fn is_pure_comment(comment: &Comment) -> bool {
+	comment.kind == CommentKind::Block && comment.text.trim() == "#__PURE__"
+}
+
+/// Moves spans from one copy of the file to another, and clears syntax contexts so the program
+/// can go through `resolver` again.
+struct Rebase {
+	from: BytePos,
+	to: BytePos,
+}
+
+impl VisitMut for Rebase {
+	fn visit_mut_span(&mut self, span: &mut Span) {
+		span.lo = rebase(span.lo, self.from, self.to);
+		span.hi = rebase(span.hi, self.from, self.to);
+	}
+
+	fn visit_mut_syntax_context(&mut self, ctxt: &mut SyntaxContext) {
+		*ctxt = SyntaxContext::empty();
+	}
+}
+
+/// The start and end of every span, where the host's comments are attached.
+#[derive(Default)]
+struct Positions(BTreeSet<BytePos>);
+
+impl Visit for Positions {
+	fn visit_span(&mut self, span: &Span) {
+		self.0.insert(span.lo);
+		self.0.insert(span.hi);
+	}
+}
+
+/// Writes every segment as `<dir>/<segment path>` (plus `.map`), and the output as JSON.
+fn write_segments(dir: &Path, output: &TransformOutput) -> std::io::Result<()> {
+	for module in &output.modules {
+		let file = dir.join(&module.path);
+		if let Some(parent) = file.parent() {
+			std::fs::create_dir_all(parent)?;
+		}
+		if module.segment.is_some() {
+			std::fs::write(&file, &module.code)?;
+			if let Some(map) = &module.map {
+				std::fs::write(format!("{}.map", file.display()), map)?;
+			}
+		} else {
+			let manifest = serde_json::to_vec(output)?;
+			std::fs::write(format!("{}.qwik.json", file.display()), manifest)?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use swc_core::common::FileName;
+	use swc_core::ecma::parser::{parse_file_as_program, EsSyntax, Syntax};
+
+	/// The diagnostic codes of `code` going through the plugin, with the host's comments.
+	fn diagnostic_codes(code: &str) -> Vec<String> {
+		let host_map: Lrc<SourceMap> = Default::default();
+		// So the host file doesn't start where the optimizer's copy does
+		host_map.new_source_file(Lrc::new(FileName::Anon), "// other file");
+		let file = host_map.new_source_file(
+			Lrc::new(FileName::Custom("/app/src/app.jsx".into())),
+			code.to_string(),
+		);
+		let comments = SingleThreadedComments::default();
+		let syntax = Syntax::Es(EsSyntax {
+			jsx: true,
+			..Default::default()
+		});
+		let program = parse_file_as_program(
+			&file,
+			syntax,
+			Default::default(),
+			Some(&comments),
+			&mut vec![],
+		)
+		.unwrap();
+		let (options, _) =
+			parse_config(Some(r#"{ "srcDir": "/app/src", "transpileJsx": true }"#)).unwrap();
+		Plugin::new(&file)
+			.transform(program, options, "app.jsx".into(), Some(&comments))
+			.diagnostics
+			.into_iter()
+			.filter_map(|diagnostic| diagnostic.code)
+			.collect()
+	}
+
+	#[test]
+	fn directives_reach_the_optimizer() {
+		let code = |directive: &str| {
+			format!(
+				r#"import {{ component$ }} from '@qwik.dev/core';
+export const App = component$(() => {{
+	{}
+	return <button passive:click preventdefault:click onClick$={{() => {{}}}} />;
+}});
+"#,
+				directive
+			)
+		};
+		assert_eq!(
+			diagnostic_codes(&code("")),
+			vec!["preventdefault-passive-check"]
+		);
+		assert_eq!(
+			diagnostic_codes(&code(
+				"// @qwik-disable-next-line preventdefault-passive-check"
+			)),
+			Vec::<String>::new()
+		);
+	}
+}
@@ -2,23 +2,63 @@
 #![deny(clippy::perf)]
 #![deny(clippy::nursery)]
 
-use js_sys::Error;
-use qwik_core::TransformModulesOptions;
+use js_sys::{Error, Reflect};
+use qwik_core::{OptimizerError, TransformModulesOptions};
 use serde::ser::Serialize;
 use serde_wasm_bindgen::{from_value, Serializer};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 pub fn transform_modules(config_val: JsValue) -> Result<JsValue, JsValue> {
-	let config: TransformModulesOptions = from_value(config_val).map_err(JsValue::from)?;
+	let config = options(config_val)?;
 
-	let result = qwik_core::transform_modules(config)
-		.map_err(|e| Error::from(JsValue::from_str(&e.to_string())))?;
+	let result = qwik_core::transform_modules(config).map_err(to_js_error)?;
 
 	let serializer = Serializer::new().serialize_maps_as_objects(true);
 	result.serialize(&serializer).map_err(JsValue::from)
 }
 
+#[wasm_bindgen]
+pub fn analyze_modules(config_val: JsValue) -> Result<JsValue, JsValue> {
+	let config = options(config_val)?;
+
+	let result = qwik_core::analyze_modules(config).map_err(to_js_error)?;
+
+	let serializer = Serializer::new().serialize_maps_as_objects(true);
+	result.serialize(&serializer).map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn module_info(config_val: JsValue) -> Result<JsValue, JsValue> {
+	let config = options(config_val)?;
+
+	let result = qwik_core::module_info(config).map_err(to_js_error)?;
+
+	let serializer = Serializer::new().serialize_maps_as_objects(true);
+	result.serialize(&serializer).map_err(JsValue::from)
+}
+
+/// The options passed from JS. Unknown fields and values that don't deserialize are rejected like
+/// the problems `validate` finds, with an `invalidOptions` error.
+fn options(config_val: JsValue) -> Result<TransformModulesOptions, JsValue> {
+	from_value(config_val).map_err(|err| {
+		to_js_error(OptimizerError::InvalidOptions {
+			message: err.to_string(),
+		})
+	})
+}
+
+/// An `Error` that carries the `kind` of the `OptimizerError` and the `file` it is about.
+fn to_js_error(err: OptimizerError) -> JsValue {
+	let js_err = Error::new(&err.to_string());
+	// Setting properties on a fresh Error object can't fail
+	let _ = Reflect::set(&js_err, &"kind".into(), &err.kind().into());
+	if let Some(file) = err.file() {
+		let _ = Reflect::set(&js_err, &"file".into(), &file.into());
+	}
+	js_err.into()
+}
+
 // #[wasm_bindgen]
 // pub fn transform_code(config_val: JsValue) -> Result<JsValue, JsValue> {
 //   let input: String = from_value(config_val).map_err(JsValue::from)?;
@@ -25,6 +25,7 @@ import {
   isServerOnlyModule,
   mightContainServerOnlyImport,
 } from './server-only-modules';
+import { addBundleSizes, mergeSizeReports } from './size-report';
 import { isVirtualId, isWin, parseId, sanitizeChunkGroupName } from './vite-utils';
 import MagicString from 'magic-string';
 import {
@@ -145,6 +146,7 @@ export function createQwikPlugin(optimizerOptions: OptimizerOptions = {}) {
     },
     inlineStylesUpToBytes: 20000,
     lint: false,
+    sizeReport: false,
     experimental: undefined,
   };
 
@@ -379,6 +381,10 @@ export function createQwikPlugin(optimizerOptions: OptimizerOptions = {}) {
       opts.lint = updatedOpts.lint;
     }
 
+    if (typeof updatedOpts.sizeReport === 'boolean') {
+      opts.sizeReport = updatedOpts.sizeReport;
+    }
+
     if ('experimental' in updatedOpts) {
       opts.experimental = undefined;
       for (const feature of updatedOpts.experimental ?? []) {
@@ -1039,6 +1045,7 @@ export function createQwikPlugin(optimizerOptions: OptimizerOptions = {}) {
         mode,
         scope: opts.scope || undefined,
         isServer,
+        sizeReport: opts.sizeReport && !isServer,
       };
 
       if (strip) {
@@ -1456,6 +1463,21 @@ export const isDev = ${JSON.stringify(isDev)};
       size: bundleGraph.length,
     };
 
+    if (opts.sizeReport) {
+      if (optimizer.sys.env === 'node') {
+        const zlib: typeof import('zlib') = await optimizer.sys.dynamicImport('node:zlib');
+        addBundleSizes(manifest, rollupBundle, outputAnalyzer.canonPath, zlib);
+      }
+      const sizeReport = mergeSizeReports(
+        Array.from(clientResults.values(), (output) => output.sizeReport)
+      );
+      ctx.emitFile({
+        fileName: Q_SIZE_REPORT_FILENAME,
+        type: 'asset',
+        source: JSON.stringify(sizeReport, null, '\t'),
+      });
+    }
+
     const manifestStr = JSON.stringify(manifest, null, '\t');
     ctx.emitFile({
       fileName: Q_MANIFEST_FILENAME,
@@ -1573,6 +1595,8 @@ const LIB_OUT_DIR = 'lib';
 
 export const Q_MANIFEST_FILENAME = 'q-manifest.json';
 
+export const Q_SIZE_REPORT_FILENAME = 'q-size-report.json';
+
 /** @public */
 export interface QwikPluginDevTools {
   /**
@@ -1640,6 +1664,14 @@ export interface QwikPluginOptions {
    * large projects. Defaults to `true`
    */
   lint?: boolean;
+  /**
+   * Measure the raw, gzip and brotli size of every module the optimizer emits for the client build,
+   * and write them to `q-size-report.json` next to `q-manifest.json`. The manifest bundles also get
+   * their `gzipSize` and `brotliSize`.
+   *
+   * Default `false`
+   */
+  sizeReport?: boolean;
   /**
    * Experimental features. These can come and go in patch releases, and their API is not guaranteed
    * to be stable between releases.
@@ -0,0 +1,59 @@
+import type { Rolldown } from 'vite';
+import type { ModuleSizes, QwikManifest, SizeReport } from '../types';
+
+const addSizes = (rollup: Record<string, ModuleSizes>, key: string, sizes: ModuleSizes) => {
+  const total = (rollup[key] ||= { raw: 0, gzip: 0, brotli: 0 });
+  total.raw += sizes.raw;
+  total.gzip += sizes.gzip;
+  total.brotli += sizes.brotli;
+};
+
+/** Merges the size reports of every transformed file into one report for the whole build. */
+export function mergeSizeReports(reports: (SizeReport | undefined)[]): SizeReport {
+  const merged: SizeReport = {
+    modules: [],
+    entries: {},
+    origins: {},
+    components: {},
+    total: { raw: 0, gzip: 0, brotli: 0 },
+  };
+  for (const report of reports) {
+    if (!report) {
+      continue;
+    }
+    merged.modules.push(...report.modules);
+    for (const [entry, sizes] of Object.entries(report.entries)) {
+      addSizes(merged.entries, entry, sizes);
+    }
+    for (const [origin, sizes] of Object.entries(report.origins)) {
+      addSizes(merged.origins, origin, sizes);
+    }
+    for (const [component, sizes] of Object.entries(report.components)) {
+      addSizes(merged.components, component, sizes);
+    }
+    merged.total.raw += report.total.raw;
+    merged.total.gzip += report.total.gzip;
+    merged.total.brotli += report.total.brotli;
+  }
+  merged.modules.sort((a, b) => a.path.localeCompare(b.path));
+  return merged;
+}
+
+/** Sets the gzip and brotli size of every manifest bundle from the code that was emitted for it. */
+export function addBundleSizes(
+  manifest: QwikManifest,
+  rollupBundle: Rolldown.OutputBundle,
+  canonPath: (fileName: string) => string,
+  zlib: typeof import('zlib')
+) {
+  for (const output of Object.values(rollupBundle)) {
+    if (output.type !== 'chunk') {
+      continue;
+    }
+    const bundle = manifest.bundles[canonPath(output.fileName)];
+    if (bundle) {
+      bundle.gzipSize = zlib.gzipSync(output.code, { level: 9 }).length;
+      bundle.brotliSize = zlib.brotliCompressSync(output.code).length;
+    }
+  }
+}
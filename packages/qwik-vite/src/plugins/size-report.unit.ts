@@ -0,0 +1,30 @@
+import { describe, expect, test } from 'vitest';
+import type { SizeReport } from '../types';
+import { mergeSizeReports } from './size-report';
+
+const report = (path: string, raw: number): SizeReport => {
+  const sizes = { raw, gzip: raw / 2, brotli: raw / 4 };
+  return {
+    modules: [{ path, segment: null, entry: path, origin: 'app.tsx', component: null, ...sizes }],
+    entries: { [path]: sizes },
+    origins: { 'app.tsx': sizes },
+    components: {},
+    total: sizes,
+  };
+};
+
+describe('mergeSizeReports', () => {
+  test('sums the rollups of every report', () => {
+    const merged = mergeSizeReports([report('b.js', 40), undefined, report('a.js', 8)]);
+    expect(merged.modules.map((m) => m.path)).toEqual(['a.js', 'b.js']);
+    expect(merged.entries['a.js']).toEqual({ raw: 8, gzip: 4, brotli: 2 });
+    expect(merged.origins['app.tsx']).toEqual({ raw: 48, gzip: 24, brotli: 12 });
+    expect(merged.total).toEqual({ raw: 48, gzip: 24, brotli: 12 });
+  });
+
+  test('does not change the reports it merges', () => {
+    const first = report('a.js', 8);
+    mergeSizeReports([first, report('b.js', 8)]);
+    expect(first.origins['app.tsx']).toEqual({ raw: 8, gzip: 4, brotli: 2 });
+  });
+});
@@ -234,6 +234,7 @@ export function qwikVite(qwikViteOpts: QwikVitePluginOptions = {}): any {
         manifestInput: qwikViteOpts.ssr?.manifestInput,
         manifestInputPath: qwikViteOpts.ssr?.manifestInputPath,
         manifestOutput: qwikViteOpts.client?.manifestOutput,
+        sizeReport: qwikViteOpts.client?.sizeReport,
       };
 
       const opts = await qwikPlugin.normalizeOptions(pluginOpts);
@@ -1096,6 +1097,13 @@ interface QwikVitePluginSSROptions extends QwikVitePluginCommonOptions {
      * Default `undefined`
      */
     manifestOutput?: (manifest: QwikManifest) => Promise<void> | void;
+    /**
+     * Write the raw, gzip and brotli size of every optimizer module to `q-size-report.json` next to
+     * `q-manifest.json`, and add `gzipSize` and `brotliSize` to the manifest bundles.
+     *
+     * Default `false`
+     */
+    sizeReport?: boolean;
   };
 
   /** @deprecated Use the `devSsrServer` option of the qwikRouter() plugin instead. */
@@ -103,6 +103,10 @@ export interface QwikSymbol {
 export interface QwikBundle {
   /** Size of the bundle */
   size: number;
+  /** Gzip size of the bundle, only present when the `sizeReport` option is enabled */
+  gzipSize?: number;
+  /** Brotli size of the bundle, only present when the `sizeReport` option is enabled */
+  brotliSize?: number;
   /** Total size of this bundle's static import graph */
   total: number;
   /** Interactivity score of the bundle */
@@ -32,6 +32,9 @@ export async function buildPlatformBinding(config: BuildConfig) {
   await execa(cmd, args, {
     stdio: 'inherit',
     cwd: napiCwd,
+    // The release profile aborts on panic, the binding needs unwinding to turn the panic of one
+    // input into a diagnostic instead of crashing the Node process
+    env: { CARGO_PROFILE_RELEASE_PANIC: 'unwind' },
   });
 
   console.log('🐯 native binding');